@@ -126,6 +126,11 @@ main() {
     cp "$binary_path" "${INSTALL_DIR}/${binary_name}"
     chmod +x "${INSTALL_DIR}/${binary_name}"
 
+    # git runs 1seed under this name for commit signing (1seed git setup)
+    if [[ $platform != windows* ]]; then
+        ln -sf "$binary_name" "${INSTALL_DIR}/1seed-ssh-sign"
+    fi
+
     # check if in PATH
     if ! echo "$PATH" | grep -q "$INSTALL_DIR"; then
         warn "${INSTALL_DIR} is not in your PATH"
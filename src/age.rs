@@ -1,26 +1,38 @@
+use crate::harden;
 use crate::seed::Seed;
-use std::io::{Read, Write};
+use std::io::{BufRead, BufWriter, Read, Write};
 use std::path::Path;
+use zeroize::Zeroizing;
 
 pub fn derive_recipient(seed: &Seed, realm: &str) -> String {
     let raw = seed.derive_32(realm, "age");
-    let secret = x25519_dalek::StaticSecret::from(*raw);
-    let public = x25519_dalek::PublicKey::from(&secret);
+    let public = x25519_public(&raw);
 
     // encode recipient using age's format
     use bech32::{ToBase32, Variant};
-    let data = public.as_bytes().to_base32();
+    let data = public.to_base32();
     bech32::encode("age", data, Variant::Bech32).expect("valid bech32")
 }
 
-pub fn derive_identity(seed: &Seed, realm: &str) -> String {
+/// Curve25519 public key for a derived secret (clamped by x25519-dalek).
+pub fn x25519_public(raw: &[u8; 32]) -> [u8; 32] {
+    let secret = x25519_dalek::StaticSecret::from(*raw);
+    x25519_dalek::PublicKey::from(&secret).to_bytes()
+}
+
+pub fn derive_identity(seed: &Seed, realm: &str) -> Zeroizing<String> {
     let raw = seed.derive_32(realm, "age");
 
-    // encode identity using age's format
+    // encode identity using age's format, writing straight into a buffer
+    // large enough that it never reallocates and leaves copies behind
     use bech32::{ToBase32, Variant};
-    let data = raw.to_base32();
-    let encoded = bech32::encode("age-secret-key-", data, Variant::Bech32).expect("valid bech32");
-    encoded.to_uppercase()
+    let mut encoded = Zeroizing::new(String::with_capacity(96));
+    let mut writer = bech32::Bech32Writer::new("age-secret-key-", Variant::Bech32, &mut *encoded)
+        .expect("valid bech32");
+    raw.write_base32(&mut writer).expect("valid bech32");
+    writer.finalize().expect("valid bech32");
+    encoded.make_ascii_uppercase();
+    encoded
 }
 
 pub fn encrypt(
@@ -33,28 +45,9 @@ pub fn encrypt(
         return Err("no recipients specified".into());
     }
 
-    let plaintext = read_input(input)?;
-
     let encryptor =
         age::Encryptor::with_recipients(recipients).ok_or("failed to create encryptor")?;
-
-    let mut ciphertext = vec![];
-
-    if armor {
-        let mut writer = encryptor.wrap_output(age::armor::ArmoredWriter::wrap_output(
-            &mut ciphertext,
-            age::armor::Format::AsciiArmor,
-        )?)?;
-        writer.write_all(&plaintext)?;
-        writer.finish().and_then(|w| w.finish())?;
-    } else {
-        let mut writer = encryptor.wrap_output(&mut ciphertext)?;
-        writer.write_all(&plaintext)?;
-        writer.finish()?;
-    }
-
-    write_output(output, &ciphertext)?;
-    Ok(())
+    encrypt_stream(encryptor, armor, input, output)
 }
 
 pub fn encrypt_passphrase(
@@ -63,26 +56,28 @@ pub fn encrypt_passphrase(
     input: Option<&Path>,
     output: Option<&Path>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let plaintext = read_input(input)?;
-
     let encryptor = age::Encryptor::with_user_passphrase(passphrase.to_string().into());
+    encrypt_stream(encryptor, armor, input, output)
+}
 
-    let mut ciphertext = vec![];
-
-    if armor {
-        let mut writer = encryptor.wrap_output(age::armor::ArmoredWriter::wrap_output(
-            &mut ciphertext,
-            age::armor::Format::AsciiArmor,
-        )?)?;
-        writer.write_all(&plaintext)?;
-        writer.finish().and_then(|w| w.finish())?;
+/// Encrypt a chunk at a time, so the input never has to fit in memory.
+fn encrypt_stream(
+    encryptor: age::Encryptor,
+    armor: bool,
+    input: Option<&Path>,
+    output: Option<&Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut reader = open_input(input)?;
+    let format = if armor {
+        age::armor::Format::AsciiArmor
     } else {
-        let mut writer = encryptor.wrap_output(&mut ciphertext)?;
-        writer.write_all(&plaintext)?;
-        writer.finish()?;
-    }
+        age::armor::Format::Binary
+    };
 
-    write_output(output, &ciphertext)?;
+    let armored = age::armor::ArmoredWriter::wrap_output(create_output(output)?, format)?;
+    let mut writer = encryptor.wrap_output(armored)?;
+    harden::copy(&mut reader, &mut writer)?;
+    writer.finish().and_then(|w| w.finish())?.flush()?;
     Ok(())
 }
 
@@ -91,27 +86,8 @@ pub fn decrypt(
     input: Option<&Path>,
     output: Option<&Path>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let mut ciphertext = read_input(input)?;
-
     let identity: age::x25519::Identity = identity.parse()?;
-
-    // handle armored input by de-armoring first
-    if ciphertext.starts_with(b"-----BEGIN AGE ENCRYPTED FILE-----") {
-        let armored_reader = age::armor::ArmoredReader::new(&ciphertext[..]);
-        ciphertext = std::io::Read::bytes(armored_reader).collect::<Result<Vec<u8>, _>>()?;
-    }
-
-    let decryptor = match age::Decryptor::new(&ciphertext[..])? {
-        age::Decryptor::Recipients(d) => d,
-        age::Decryptor::Passphrase(_) => return Err("encrypted with passphrase, use -p".into()),
-    };
-
-    let mut plaintext = vec![];
-    let mut reader = decryptor.decrypt(std::iter::once(&identity as &dyn age::Identity))?;
-    reader.read_to_end(&mut plaintext)?;
-
-    write_output(output, &plaintext)?;
-    Ok(())
+    decrypt_with_identity(&identity, input, output)
 }
 
 pub fn decrypt_with_file(
@@ -119,28 +95,23 @@ pub fn decrypt_with_file(
     input: Option<&Path>,
     output: Option<&Path>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let mut ciphertext = read_input(input)?;
-
-    let key_content = std::fs::read_to_string(key_file)?;
+    let key_content = Zeroizing::new(std::fs::read_to_string(key_file)?);
     let identity: age::x25519::Identity = key_content.trim().parse()?;
+    decrypt_with_identity(&identity, input, output)
+}
 
-    // handle armored input by de-armoring first
-    if ciphertext.starts_with(b"-----BEGIN AGE ENCRYPTED FILE-----") {
-        let armored_reader = age::armor::ArmoredReader::new(&ciphertext[..]);
-        ciphertext = std::io::Read::bytes(armored_reader).collect::<Result<Vec<u8>, _>>()?;
-    }
-
-    let decryptor = match age::Decryptor::new(&ciphertext[..])? {
+fn decrypt_with_identity(
+    identity: &age::x25519::Identity,
+    input: Option<&Path>,
+    output: Option<&Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let decryptor = match open_decryptor(input)? {
         age::Decryptor::Recipients(d) => d,
         age::Decryptor::Passphrase(_) => return Err("encrypted with passphrase, use -p".into()),
     };
 
-    let mut plaintext = vec![];
-    let mut reader = decryptor.decrypt(std::iter::once(&identity as &dyn age::Identity))?;
-    reader.read_to_end(&mut plaintext)?;
-
-    write_output(output, &plaintext)?;
-    Ok(())
+    let reader = decryptor.decrypt(std::iter::once(identity as &dyn age::Identity))?;
+    decrypt_stream(reader, output)
 }
 
 pub fn decrypt_passphrase(
@@ -148,25 +119,53 @@ pub fn decrypt_passphrase(
     input: Option<&Path>,
     output: Option<&Path>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let mut ciphertext = read_input(input)?;
-
-    // handle armored input by de-armoring first
-    if ciphertext.starts_with(b"-----BEGIN AGE ENCRYPTED FILE-----") {
-        let armored_reader = age::armor::ArmoredReader::new(&ciphertext[..]);
-        ciphertext = std::io::Read::bytes(armored_reader).collect::<Result<Vec<u8>, _>>()?;
-    }
-
-    let decryptor = match age::Decryptor::new(&ciphertext[..])? {
+    let decryptor = match open_decryptor(input)? {
         age::Decryptor::Recipients(_) => return Err("not encrypted with passphrase".into()),
         age::Decryptor::Passphrase(d) => d,
     };
 
-    let mut plaintext = vec![];
-    let mut reader = decryptor.decrypt(&passphrase.to_string().into(), None)?;
-    reader.read_to_end(&mut plaintext)?;
+    let reader = decryptor.decrypt(&passphrase.to_string().into(), None)?;
+    decrypt_stream(reader, output)
+}
 
-    write_output(output, &plaintext)?;
-    Ok(())
+/// Read the age header, de-armoring the input if it is armored.
+fn open_decryptor(
+    input: Option<&Path>,
+) -> Result<age::Decryptor<impl BufRead>, Box<dyn std::error::Error>> {
+    let reader = age::armor::ArmoredReader::new(open_input(input)?);
+    Ok(age::Decryptor::new_buffered(reader)?)
+}
+
+/// Each chunk is authenticated before it is written, but a truncated or
+/// tampered file only fails partway through. An output file is therefore
+/// written under a temporary name and renamed once everything checked out,
+/// so a failed decryption never leaves partial plaintext behind.
+fn decrypt_stream(
+    mut reader: impl Read,
+    output: Option<&Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(path) = output else {
+        let mut stdout = std::io::stdout().lock();
+        harden::copy(&mut reader, &mut stdout)?;
+        stdout.flush()?;
+        return Ok(());
+    };
+
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".partial");
+    let partial = std::path::PathBuf::from(partial);
+
+    let result = (|| -> Result<(), Box<dyn std::error::Error>> {
+        let mut writer = BufWriter::new(std::fs::File::create(&partial)?);
+        harden::copy(&mut reader, &mut writer)?;
+        writer.flush()?;
+        std::fs::rename(&partial, path)?;
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&partial);
+    }
+    result
 }
 
 pub fn parse_recipient(
@@ -194,20 +193,16 @@ pub fn parse_recipients_file(
     Ok(recipients)
 }
 
-fn read_input(path: Option<&Path>) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
-    match path {
-        Some(p) => Ok(std::fs::read(p)?),
-        None => {
-            let mut buf = vec![];
-            std::io::stdin().read_to_end(&mut buf)?;
-            Ok(buf)
-        }
-    }
+fn open_input(path: Option<&Path>) -> std::io::Result<Box<dyn Read>> {
+    Ok(match path {
+        Some(p) => Box::new(std::fs::File::open(p)?),
+        None => Box::new(std::io::stdin().lock()),
+    })
 }
 
-fn write_output(path: Option<&Path>, data: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
-    match path {
-        Some(p) => Ok(std::fs::write(p, data)?),
-        None => Ok(std::io::stdout().write_all(data)?),
-    }
+fn create_output(path: Option<&Path>) -> std::io::Result<Box<dyn Write>> {
+    Ok(match path {
+        Some(p) => Box::new(BufWriter::new(std::fs::File::create(p)?)),
+        None => Box::new(std::io::stdout().lock()),
+    })
 }
@@ -0,0 +1,333 @@
+//! BIP32 hierarchical deterministic keys over secp256k1, with SLIP-132
+//! serialization.
+
+use hmac::{Hmac, Mac};
+use k256::elliptic_curve::PrimeField;
+use k256::{NonZeroScalar, Scalar, SecretKey};
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256, Sha512};
+use zeroize::Zeroizing;
+
+const HARDENED: u32 = 1 << 31;
+
+/// SLIP-132 version bytes; the prefix tells wallets which script type the
+/// account uses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Version {
+    /// P2PKH (BIP44) and anything else
+    X,
+    /// P2WPKH nested in P2SH (BIP49)
+    Y,
+    /// Native P2WPKH (BIP84)
+    Z,
+    /// Testnet X
+    T,
+    /// Testnet Y
+    U,
+    /// Testnet Z
+    V,
+}
+
+impl Version {
+    /// The usual encoding for a BIP44/49/84 path: purpose picks the script
+    /// type and coin type 1 means testnet.
+    pub fn for_path(path: &[u32]) -> Self {
+        let testnet = path.get(1) == Some(&(HARDENED | 1));
+        match (path.first().map(|p| p & !HARDENED), testnet) {
+            (Some(49), false) => Version::Y,
+            (Some(84), false) => Version::Z,
+            (Some(49), true) => Version::U,
+            (Some(84), true) => Version::V,
+            (_, true) => Version::T,
+            (_, false) => Version::X,
+        }
+    }
+
+    /// Version for serialized bytes, and whether they hold a private key.
+    fn from_bytes(bytes: [u8; 4]) -> Option<(Self, bool)> {
+        [
+            Version::X,
+            Version::Y,
+            Version::Z,
+            Version::T,
+            Version::U,
+            Version::V,
+        ]
+        .into_iter()
+        .flat_map(|v| [(v, false), (v, true)])
+        .find(|&(v, private)| v.bytes(private) == bytes)
+    }
+
+    fn bytes(self, private: bool) -> [u8; 4] {
+        let v: u32 = match (self, private) {
+            (Version::X, false) => 0x0488_b21e,
+            (Version::X, true) => 0x0488_ade4,
+            (Version::Y, false) => 0x049d_7cb2,
+            (Version::Y, true) => 0x049d_7878,
+            (Version::Z, false) => 0x04b2_4746,
+            (Version::Z, true) => 0x04b2_430c,
+            (Version::T, false) => 0x0435_87cf,
+            (Version::T, true) => 0x0435_8394,
+            (Version::U, false) => 0x044a_5262,
+            (Version::U, true) => 0x044a_4e28,
+            (Version::V, false) => 0x045f_1cf6,
+            (Version::V, true) => 0x045f_18bc,
+        };
+        v.to_be_bytes()
+    }
+}
+
+pub struct ExtendedKey {
+    depth: u8,
+    parent_fingerprint: [u8; 4],
+    child_number: u32,
+    chain_code: Zeroizing<[u8; 32]>,
+    key: SecretKey,
+}
+
+impl ExtendedKey {
+    /// Master key from a BIP39 seed (or any 16-64 byte seed).
+    pub fn master(seed: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut mac = Hmac::<Sha512>::new_from_slice(b"Bitcoin seed").expect("any key length");
+        mac.update(seed);
+        let (key, chain_code) = split(&mac.finalize().into_bytes());
+
+        Ok(ExtendedKey {
+            depth: 0,
+            parent_fingerprint: [0; 4],
+            child_number: 0,
+            chain_code,
+            key: SecretKey::from_bytes((&*key).into()).map_err(|_| "invalid master key")?,
+        })
+    }
+
+    /// Parse a serialized extended private key (xprv, zprv, ...).
+    pub fn from_private_string(encoded: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let invalid = "invalid extended private key";
+        let data = Zeroizing::new(
+            bs58::decode(encoded.trim())
+                .with_check(None)
+                .into_vec()
+                .map_err(|_| invalid)?,
+        );
+        if data.len() != 78 || data[45] != 0 {
+            return Err(invalid.into());
+        }
+        match Version::from_bytes(data[..4].try_into().expect("four bytes")) {
+            Some((_, true)) => {}
+            Some((_, false)) => return Err("expected a private key, not a public one".into()),
+            None => return Err(invalid.into()),
+        }
+
+        let mut chain_code = Zeroizing::new([0u8; 32]);
+        chain_code.copy_from_slice(&data[13..45]);
+        Ok(ExtendedKey {
+            depth: data[4],
+            parent_fingerprint: data[5..9].try_into().expect("four bytes"),
+            child_number: u32::from_be_bytes(data[9..13].try_into().expect("four bytes")),
+            chain_code,
+            key: SecretKey::from_slice(&data[46..]).map_err(|_| invalid)?,
+        })
+    }
+
+    pub fn derive_path(&self, path: &[u32]) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut key = ExtendedKey {
+            depth: self.depth,
+            parent_fingerprint: self.parent_fingerprint,
+            child_number: self.child_number,
+            chain_code: self.chain_code.clone(),
+            key: self.key.clone(),
+        };
+        for &index in path {
+            key = key.child(index)?;
+        }
+        Ok(key)
+    }
+
+    /// CKDpriv. The spec's "proceed with the next index" case (probability
+    /// below 2^-127) is reported as an error instead.
+    fn child(&self, index: u32) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut mac = Hmac::<Sha512>::new_from_slice(&*self.chain_code).expect("any key length");
+        if index & HARDENED != 0 {
+            mac.update(&[0]);
+            mac.update(&self.key.to_bytes());
+        } else {
+            mac.update(&self.public_key());
+        }
+        mac.update(&index.to_be_bytes());
+        let (tweak, chain_code) = split(&mac.finalize().into_bytes());
+
+        let tweak: Option<Scalar> = Scalar::from_repr((*tweak).into()).into();
+        let key: Option<NonZeroScalar> =
+            tweak.and_then(|t| NonZeroScalar::new(t + *self.key.to_nonzero_scalar()).into());
+        let key = key.ok_or_else(|| format!("invalid child key at index {index}"))?;
+
+        Ok(ExtendedKey {
+            depth: self.depth.checked_add(1).ok_or("path too deep")?,
+            parent_fingerprint: self.fingerprint(),
+            child_number: index,
+            chain_code,
+            key: SecretKey::from(key),
+        })
+    }
+
+    /// Raw 32-byte private key.
+    pub fn secret_bytes(&self) -> Zeroizing<[u8; 32]> {
+        Zeroizing::new(self.key.to_bytes().into())
+    }
+
+    /// Compressed SEC1 public key.
+    pub fn public_key(&self) -> [u8; 33] {
+        use k256::elliptic_curve::sec1::ToEncodedPoint;
+        let point = self.key.public_key().to_encoded_point(true);
+        point.as_bytes().try_into().expect("compressed point")
+    }
+
+    /// First four bytes of HASH160 of the public key.
+    pub fn fingerprint(&self) -> [u8; 4] {
+        let hash = Ripemd160::digest(Sha256::digest(self.public_key()));
+        hash[..4].try_into().expect("four bytes")
+    }
+
+    pub fn to_public_string(&self, version: Version) -> String {
+        bs58::encode(&*self.serialize(version.bytes(false), &self.public_key()))
+            .with_check()
+            .into_string()
+    }
+
+    pub fn to_private_string(&self, version: Version) -> Zeroizing<String> {
+        let mut key = Zeroizing::new([0u8; 33]);
+        key[1..].copy_from_slice(&self.key.to_bytes());
+        Zeroizing::new(
+            bs58::encode(&*self.serialize(version.bytes(true), &key))
+                .with_check()
+                .into_string(),
+        )
+    }
+
+    fn serialize(&self, version: [u8; 4], key: &[u8; 33]) -> Zeroizing<Vec<u8>> {
+        let mut out = Zeroizing::new(Vec::with_capacity(78));
+        out.extend_from_slice(&version);
+        out.push(self.depth);
+        out.extend_from_slice(&self.parent_fingerprint);
+        out.extend_from_slice(&self.child_number.to_be_bytes());
+        out.extend_from_slice(&*self.chain_code);
+        out.extend_from_slice(key);
+        out
+    }
+}
+
+fn split(i: &[u8]) -> (Zeroizing<[u8; 32]>, Zeroizing<[u8; 32]>) {
+    let mut left = Zeroizing::new([0u8; 32]);
+    let mut right = Zeroizing::new([0u8; 32]);
+    left.copy_from_slice(&i[..32]);
+    right.copy_from_slice(&i[32..]);
+    (left, right)
+}
+
+/// Parse `m/84'/0'/0'`; hardened steps are marked with `'`, `h` or `H`.
+pub fn parse_path(path: &str) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
+    let invalid = || format!("invalid derivation path '{path}'");
+
+    let mut parts = path.split('/');
+    if parts.next() != Some("m") {
+        return Err(invalid().into());
+    }
+    parts
+        .map(|part| {
+            let (number, hardened) = match part.strip_suffix(['\'', 'h', 'H']) {
+                Some(number) => (number, true),
+                None => (part, false),
+            };
+            let index: u32 = number.parse().map_err(|_| invalid())?;
+            if index >= HARDENED {
+                return Err(invalid().into());
+            }
+            Ok(if hardened { index | HARDENED } else { index })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn derive(seed_hex: &str, path: &str) -> ExtendedKey {
+        let master = ExtendedKey::master(&hex::decode(seed_hex).unwrap()).unwrap();
+        master.derive_path(&parse_path(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn bip32_vector_1() {
+        let seed = "000102030405060708090a0b0c0d0e0f";
+
+        let m = derive(seed, "m");
+        assert_eq!(
+            m.to_public_string(Version::X),
+            "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8"
+        );
+        assert_eq!(
+            m.to_private_string(Version::X).as_str(),
+            "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi"
+        );
+
+        let child = derive(seed, "m/0H/1/2H/2/1000000000");
+        assert_eq!(
+            child.to_public_string(Version::X),
+            "xpub6H1LXWLaKsWFhvm6RVpEL9P4KfRZSW7abD2ttkWP3SSQvnyA8FSVqNTEcYFgJS2UaFcxupHiYkro49S8yGasTvXEYBVPamhGW6cFJodrTHy"
+        );
+    }
+
+    #[test]
+    fn bip84_vector() {
+        let mnemonic = bip39::Mnemonic::parse(
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+        )
+        .unwrap();
+        let seed = hex::encode(mnemonic.to_seed(""));
+        let path = parse_path("m/84'/0'/0'").unwrap();
+        let account = derive(&seed, "m/84'/0'/0'");
+
+        assert_eq!(hex::encode(derive(&seed, "m").fingerprint()), "73c5da0a");
+        assert_eq!(Version::for_path(&path), Version::Z);
+        assert_eq!(
+            account.to_public_string(Version::Z),
+            "zpub6rFR7y4Q2AijBEqTUquhVz398htDFrtymD9xYYfG1m4wAcvPhXNfE3EfH1r1ADqtfSdVCToUG868RvUUkgDKf31mGDtKsAYz2oz2AGutZYs"
+        );
+        assert_eq!(
+            account.to_private_string(Version::Z).as_str(),
+            "zprvAdG4iTXWBoARxkkzNpNh8r6Qag3irQB8PzEMkAFeTRXxHpbF9z4QgEvBRmfvqWvGp42t42nvgGpNgYSJA9iefm1yYNZKEm7z6qUWCroSQnE"
+        );
+    }
+
+    #[test]
+    fn private_string_round_trip() {
+        let encoded = "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi";
+        let key = ExtendedKey::from_private_string(encoded).unwrap();
+        assert_eq!(key.to_private_string(Version::X).as_str(), encoded);
+
+        let public = key.to_public_string(Version::X);
+        assert!(ExtendedKey::from_private_string(&public).is_err());
+        assert!(ExtendedKey::from_private_string(&encoded[1..]).is_err());
+    }
+
+    #[test]
+    fn paths() {
+        assert_eq!(
+            parse_path("m/44h/1'/0").unwrap(),
+            vec![44 | HARDENED, 1 | HARDENED, 0]
+        );
+        assert!(parse_path("m").unwrap().is_empty());
+        assert!(parse_path("44'/0'").is_err());
+        assert!(parse_path("m/2147483648").is_err());
+        assert!(parse_path("m//0").is_err());
+        assert_eq!(
+            Version::for_path(&parse_path("m/49'/1'/0'").unwrap()),
+            Version::U
+        );
+        assert_eq!(
+            Version::for_path(&parse_path("m/44'/0'/0'").unwrap()),
+            Version::X
+        );
+    }
+}
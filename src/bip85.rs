@@ -0,0 +1,188 @@
+//! BIP85 deterministic entropy from a BIP32 root key.
+
+use crate::bip32::ExtendedKey;
+use crate::derive;
+use base64::Engine;
+use bip39::{Language, Mnemonic};
+use hmac::{Hmac, Mac};
+use sha2::Sha512;
+use zeroize::Zeroizing;
+
+const PURPOSE: u32 = 83696968;
+const HARDENED: u32 = 1 << 31;
+
+const APP_BIP39: u32 = 39;
+const APP_HEX: u32 = 128169;
+const APP_PWD64: u32 = 707764;
+const APP_PWD85: u32 = 707785;
+
+/// RFC 1924 alphabet, as used by BIP85 (and Python's `base64.b85encode`)
+const BASE85: &[u8; 85] =
+    b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz!#$%&()*+-;<=>?@^_`{|}~";
+
+/// 64 bytes of entropy for the hardened path `m/83696968'/{path}'`.
+fn entropy(
+    root: &ExtendedKey,
+    path: &[u32],
+) -> Result<Zeroizing<[u8; 64]>, Box<dyn std::error::Error>> {
+    let full: Vec<u32> = std::iter::once(PURPOSE)
+        .chain(path.iter().copied())
+        .map(|i| i | HARDENED)
+        .collect();
+    let key = root.derive_path(&full)?.secret_bytes();
+
+    let mut mac = Hmac::<Sha512>::new_from_slice(b"bip-entropy-from-k").expect("any key length");
+    mac.update(&*key);
+    Ok(Zeroizing::new(mac.finalize().into_bytes().into()))
+}
+
+fn check_index(index: u32) -> Result<(), Box<dyn std::error::Error>> {
+    if index >= HARDENED {
+        return Err(format!("index must be below {HARDENED}").into());
+    }
+    Ok(())
+}
+
+/// BIP85 language codes; they are part of the derivation path.
+fn language_code(language: Language) -> u32 {
+    match language {
+        Language::English => 0,
+        Language::Japanese => 1,
+        Language::Korean => 2,
+        Language::Spanish => 3,
+        Language::SimplifiedChinese => 4,
+        Language::TraditionalChinese => 5,
+        Language::French => 6,
+        Language::Italian => 7,
+        Language::Czech => 8,
+        Language::Portuguese => 9,
+    }
+}
+
+pub fn mnemonic(
+    root: &ExtendedKey,
+    words: usize,
+    language: Language,
+    index: u32,
+) -> Result<Zeroizing<String>, Box<dyn std::error::Error>> {
+    if ![12, 15, 18, 21, 24].contains(&words) {
+        return Err("word count must be 12, 15, 18, 21, or 24".into());
+    }
+    check_index(index)?;
+
+    let path = [APP_BIP39, language_code(language), words as u32, index];
+    let entropy = entropy(root, &path)?;
+    let mnemonic = Mnemonic::from_entropy_in(language, &entropy[..words * 4 / 3])?;
+    Ok(derive::phrase(&mnemonic))
+}
+
+pub fn hex(
+    root: &ExtendedKey,
+    bytes: usize,
+    index: u32,
+) -> Result<Zeroizing<String>, Box<dyn std::error::Error>> {
+    if !(16..=64).contains(&bytes) {
+        return Err("byte count must be between 16 and 64".into());
+    }
+    check_index(index)?;
+
+    let entropy = entropy(root, &[APP_HEX, bytes as u32, index])?;
+    Ok(Zeroizing::new(hex::encode(&entropy[..bytes])))
+}
+
+pub fn pwd64(
+    root: &ExtendedKey,
+    length: usize,
+    index: u32,
+) -> Result<Zeroizing<String>, Box<dyn std::error::Error>> {
+    if !(20..=86).contains(&length) {
+        return Err("password length must be between 20 and 86".into());
+    }
+    check_index(index)?;
+
+    let entropy = entropy(root, &[APP_PWD64, length as u32, index])?;
+    let mut encoded = Zeroizing::new(base64::engine::general_purpose::STANDARD.encode(*entropy));
+    encoded.truncate(length);
+    Ok(encoded)
+}
+
+pub fn pwd85(
+    root: &ExtendedKey,
+    length: usize,
+    index: u32,
+) -> Result<Zeroizing<String>, Box<dyn std::error::Error>> {
+    if !(10..=80).contains(&length) {
+        return Err("password length must be between 10 and 80".into());
+    }
+    check_index(index)?;
+
+    let entropy = entropy(root, &[APP_PWD85, length as u32, index])?;
+    let mut encoded = Zeroizing::new(String::with_capacity(80));
+    for chunk in entropy.chunks(4) {
+        let mut n = u32::from_be_bytes(chunk.try_into().expect("four bytes"));
+        let mut digits = [0u8; 5];
+        for digit in digits.iter_mut().rev() {
+            *digit = BASE85[(n % 85) as usize];
+            n /= 85;
+        }
+        encoded.extend(digits.iter().map(|&d| d as char));
+    }
+    encoded.truncate(length);
+    Ok(encoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // test vectors from BIP85
+    const ROOT: &str = "xprv9s21ZrQH143K2LBWUUQRFXhucrQqBpKdRRxNVq2zBqsx8HVqFk2uYo8kmbaLLHRdqtQpUm98uKfu3vca1LqdGhUtyoFnCNkfmXRyPXLjbKb";
+
+    fn root() -> ExtendedKey {
+        ExtendedKey::from_private_string(ROOT).unwrap()
+    }
+
+    #[test]
+    fn entropy_vectors() {
+        assert_eq!(
+            hex::encode(*entropy(&root(), &[0, 0]).unwrap()),
+            "efecfbccffea313214232d29e71563d941229afb4338c21f9517c41aaa0d16f00b83d2a09ef747e7a64e8e2bd5a14869e693da66ce94ac2da570ab7ee48618f7"
+        );
+        assert_eq!(
+            hex::encode(*entropy(&root(), &[0, 1]).unwrap()),
+            "70c6e3e8ebee8dc4c0dbba66076819bb8c09672527c4277ca8729532ad711872218f826919f6b67218adde99018a6df9095ab2b58d803b5b93ec9802085a690e"
+        );
+    }
+
+    #[test]
+    fn application_vectors() {
+        assert_eq!(
+            mnemonic(&root(), 12, Language::English, 0)
+                .unwrap()
+                .as_str(),
+            "girl mad pet galaxy egg matter matrix prison refuse sense ordinary nose"
+        );
+        assert_eq!(
+            mnemonic(&root(), 24, Language::English, 0).unwrap().as_str(),
+            "puppy ocean match cereal symbol another shed magic wrap hammer bulb intact gadget divorce twin tonight reason outdoor destroy simple truth cigar social volcano"
+        );
+        assert_eq!(
+            hex(&root(), 64, 0).unwrap().as_str(),
+            "492db4698cf3b73a5a24998aa3e9d7fa96275d85724a91e71aa2d645442f878555d078fd1f1f67e368976f04137b1f7a0d19232136ca50c44614af72b5582a5c"
+        );
+        assert_eq!(
+            pwd64(&root(), 21, 0).unwrap().as_str(),
+            "dKLoepugzdVJvdL56ogNV"
+        );
+        assert_eq!(pwd85(&root(), 12, 0).unwrap().as_str(), "_s`{TW89)i4`");
+    }
+
+    #[test]
+    fn out_of_range_rejected() {
+        assert!(mnemonic(&root(), 13, Language::English, 0).is_err());
+        assert!(hex(&root(), 65, 0).is_err());
+        assert!(pwd64(&root(), 19, 0).is_err());
+        assert!(pwd85(&root(), 81, 0).is_err());
+        assert!(hex(&root(), 16, HARDENED).is_err());
+    }
+}
@@ -1,9 +1,13 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use std::io::Write;
 use std::path::PathBuf;
+use zeroize::Zeroizing;
 
-use crate::seed::{Seed, SeedSource};
-use crate::{age, derive, password, sign, ssh, update};
+use crate::seed::{Backend, Seed, SeedSource};
+use crate::{
+    age, bip32, bip85, derive, doctor, ec, envelope, git, harden, jwt, minisign, nostr, otp,
+    paseto, password, pgp, rsa, seed, sign, signify, ssh, update, wg, x509,
+};
 
 #[derive(Parser)]
 #[command(name = "1seed")]
@@ -18,20 +22,46 @@ use crate::{age, derive, password, sign, ssh, update};
     1seed --realm work ssh add       Add work SSH key to agent
     echo secret | 1seed age encrypt  Encrypt to self
     1seed derive password github.com Derive password
+    1seed otp code GitHub:alice      Current TOTP code
 
 ENVIRONMENT:
     SEED_FILE        Override: use specific file
     SEED_NO_KEYRING  Use ~/.1seed only (bypass keyring)
     SEED_REALM       Default realm (default: \"default\")
+    SEED_REALMS      Realms shown by status (comma-separated)
+    SEED_ALLOW_INSECURE  Load seed files readable by other users
+    SEED_HARDEN      Hardened mode (same as --harden)
 
 STORAGE:
-    Priority: SEED_FILE > keyring > ~/.1seed > prompt
+    Priority: SEED_FILE > keyring > ~/.1seed > ~/.1seed.age (prompts)
     Keyring: macOS Keychain, Linux Secret Service, Windows Credential Manager
 ")]
 pub struct Cli {
     #[arg(long, global = true, env = "SEED_REALM")]
     pub realm: Option<String>,
 
+    /// Load seed files even if other users can read them
+    #[arg(
+        long,
+        global = true,
+        env = "SEED_ALLOW_INSECURE",
+        value_parser = clap::builder::FalseyValueParser::new()
+    )]
+    pub allow_insecure: bool,
+
+    /// Disable core dumps and lock secrets in memory
+    #[arg(
+        long,
+        global = true,
+        env = "SEED_HARDEN",
+        value_parser = clap::builder::FalseyValueParser::new()
+    )]
+    pub harden: bool,
+
+    /// Run pub commands and status across several realms (comma-separated)
+    #[arg(long, global = true, value_delimiter = ',')]
+    pub realms: Vec<String>,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -50,18 +80,66 @@ pub enum Commands {
         action: SshAction,
     },
 
+    /// Commit signing with the SSH key
+    Git {
+        #[command(subcommand)]
+        action: GitAction,
+    },
+
+    /// WireGuard keys and configs
+    Wg {
+        #[command(subcommand)]
+        action: WgAction,
+    },
+
+    /// OpenPGP keys for gpg
+    Pgp {
+        #[command(subcommand)]
+        action: PgpAction,
+    },
+
+    /// X.509 certificates from a per-realm CA
+    X509 {
+        #[command(subcommand)]
+        action: X509Action,
+    },
+
+    /// Nostr keys and event signing
+    Nostr {
+        #[command(subcommand)]
+        action: NostrAction,
+    },
+
     /// Ed25519 signing keys and operations
     Sign {
         #[command(subcommand)]
         action: SignAction,
     },
 
+    /// JSON Web Tokens signed with the Ed25519 signing key
+    Jwt {
+        #[command(subcommand)]
+        action: JwtAction,
+    },
+
+    /// PASETO v4 tokens: signed (public) or encrypted (local)
+    Paseto {
+        #[command(subcommand)]
+        action: PasetoAction,
+    },
+
     /// Derive passwords, mnemonics, and raw bytes
     Derive {
         #[command(subcommand)]
         action: DeriveAction,
     },
 
+    /// One-time password secrets and codes (TOTP/HOTP)
+    Otp {
+        #[command(subcommand)]
+        action: OtpAction,
+    },
+
     /// Initialize: store seed in OS keychain
     Init {
         #[arg(short, long)]
@@ -74,6 +152,12 @@ pub enum Commands {
         from_file: Option<PathBuf>,
     },
 
+    /// Manage where the seed is stored
+    Seed {
+        #[command(subcommand)]
+        action: SeedAction,
+    },
+
     /// Remove seed from OS keychain
     Forget {
         #[arg(long)]
@@ -83,6 +167,16 @@ pub enum Commands {
     /// Show status and derived keys
     Status,
 
+    /// Restrict seed files to owner-only access (0600)
+    FixPerms,
+
+    /// Check storage, permissions and agent setup
+    Doctor {
+        /// Also check GitHub for a newer release
+        #[arg(long)]
+        online: bool,
+    },
+
     /// Update to latest release from GitHub
     Update {
         #[arg(long)]
@@ -90,6 +184,25 @@ pub enum Commands {
     },
 }
 
+#[derive(Subcommand)]
+pub enum SeedAction {
+    /// Move the seed to another storage backend
+    Move {
+        #[arg(long, value_enum)]
+        to: MoveTarget,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum MoveTarget {
+    /// OS keychain
+    Keyring,
+    /// ~/.1seed (mode 0600)
+    File,
+    /// ~/.1seed.age, encrypted with a passphrase
+    EncryptedFile,
+}
+
 #[derive(Subcommand)]
 pub enum AgeAction {
     /// Show age public key
@@ -139,209 +252,1873 @@ pub enum AgeAction {
 #[derive(Subcommand)]
 pub enum SshAction {
     /// Show SSH public key
-    Pub,
+    Pub {
+        #[arg(short = 't', long = "type", value_enum, default_value = "ed25519")]
+        key_type: SshKeyType,
+
+        /// RSA modulus size: 2048, 3072 or 4096
+        #[arg(short, long, default_value_t = 3072)]
+        bits: usize,
+    },
 
     /// Show SSH private key
-    Key,
+    Key {
+        #[arg(long, value_enum, default_value = "openssh")]
+        format: SshKeyFormat,
+
+        #[arg(short = 't', long = "type", value_enum, default_value = "ed25519")]
+        key_type: SshKeyType,
+
+        /// RSA modulus size: 2048, 3072 or 4096
+        #[arg(short, long, default_value_t = 3072)]
+        bits: usize,
+    },
 
     /// Add SSH key to agent
     Add {
+        #[arg(short = 't', long = "type", value_enum, default_value = "ed25519")]
+        key_type: SshKeyType,
+
+        /// RSA modulus size: 2048, 3072 or 4096
+        #[arg(short, long, default_value_t = 3072)]
+        bits: usize,
+
         #[arg(short, long)]
         lifetime: Option<u32>,
 
         #[arg(short, long)]
         confirm: bool,
     },
+
+    /// Sign a file (SSHSIG, like ssh-keygen -Y sign)
+    Sign {
+        /// Signature namespace, e.g. git or file
+        #[arg(short, long)]
+        namespace: String,
+
+        #[arg(short = 't', long = "type", value_enum, default_value = "ed25519")]
+        key_type: SshKeyType,
+
+        /// RSA modulus size: 2048, 3072 or 4096
+        #[arg(short, long, default_value_t = 3072)]
+        bits: usize,
+
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// File to sign (default: stdin)
+        file: Option<PathBuf>,
+    },
+
+    /// Verify an SSHSIG signature (like ssh-keygen -Y verify)
+    Verify {
+        /// Signature file
+        #[arg(short, long)]
+        signature: PathBuf,
+
+        /// Signature namespace, e.g. git or file
+        #[arg(short, long)]
+        namespace: String,
+
+        /// allowed_signers file (default: only the realm's key is trusted)
+        #[arg(short = 'f', long)]
+        allowed_signers: Option<PathBuf>,
+
+        /// Principal that must be allowed to sign
+        #[arg(short = 'I', long)]
+        identity: Option<String>,
+
+        /// Signed file (default: stdin)
+        file: Option<PathBuf>,
+    },
+
+    /// Show a host's known_hosts line, or install its host key
+    Hostkey {
+        #[arg(long)]
+        host: String,
+
+        /// Names for the known_hosts line, comma-separated (default: host)
+        #[arg(long, value_delimiter = ',')]
+        names: Vec<String>,
+
+        /// Write ssh_host_ed25519_key{,.pub} into --dir
+        #[arg(long)]
+        install: bool,
+
+        #[arg(long, default_value = "/etc/ssh")]
+        dir: PathBuf,
+    },
+
+    /// SSH certificate authority
+    Ca {
+        #[command(subcommand)]
+        action: SshCaAction,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum SshKeyType {
+    Ed25519,
+    P256,
+    Rsa,
+}
+
+impl SshKeyType {
+    fn key_type(self, bits: usize) -> Result<ssh::KeyType, Box<dyn std::error::Error>> {
+        Ok(match self {
+            SshKeyType::Ed25519 => ssh::KeyType::Ed25519,
+            SshKeyType::P256 => ssh::KeyType::P256,
+            SshKeyType::Rsa if rsa::SIZES.contains(&bits) => ssh::KeyType::Rsa(bits),
+            SshKeyType::Rsa => {
+                return Err(
+                    format!("unsupported RSA key size {bits}: use 2048, 3072 or 4096").into(),
+                )
+            }
+        })
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum SshKeyFormat {
+    Openssh,
+    Pkcs8,
 }
 
 #[derive(Subcommand)]
-pub enum SignAction {
-    /// Show signing public key
+pub enum SshCaAction {
+    /// Show CA public key
     Pub,
 
-    /// Sign data
-    Data {
+    /// Sign a public key, printing the certificate
+    Sign {
+        /// Public key file (default: stdin)
+        file: Option<PathBuf>,
+
+        /// Principals (user or host names, comma-separated)
+        #[arg(short = 'n', long, value_delimiter = ',', required = true)]
+        principals: Vec<String>,
+
+        /// Validity interval: +8h, -5m:+1d, 20250101Z:forever, always
+        #[arg(short = 'V', long, default_value = "+1d", allow_hyphen_values = true)]
+        validity: String,
+
+        /// Issue a host certificate instead of a user certificate
+        #[arg(long)]
+        host: bool,
+
+        #[arg(short = 'z', long, default_value_t = 0)]
+        serial: u64,
+
+        /// Key ID logged by sshd (default: principals)
+        #[arg(short = 'I', long)]
+        key_id: Option<String>,
+
+        /// Critical option NAME=VALUE (e.g. force-command=...), repeatable
+        #[arg(short = 'O', long = "option", action = clap::ArgAction::Append)]
+        options: Vec<String>,
+
+        /// Extension NAME[=VALUE], repeatable; replaces the default set
+        #[arg(long = "extension", action = clap::ArgAction::Append)]
+        extensions: Vec<String>,
+
         #[arg(short, long)]
         output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum GitAction {
+    /// Sign commits and tags with the realm's SSH key
+    Setup {
+        /// Configure ~/.gitconfig instead of the current repository
+        #[arg(long)]
+        global: bool,
 
+        /// Email to trust the key for (default: git's user.email)
         #[arg(long)]
-        binary: bool,
+        email: Option<String>,
 
-        file: Option<PathBuf>,
+        /// allowed_signers file to add the key to (default: in the git
+        /// directory, or ~/.config/git/allowed_signers with --global)
+        #[arg(long)]
+        allowed_signers: Option<PathBuf>,
+
+        #[arg(short = 't', long = "type", value_enum, default_value = "ed25519")]
+        key_type: SshKeyType,
+
+        /// RSA modulus size: 2048, 3072 or 4096
+        #[arg(short, long, default_value_t = 3072)]
+        bits: usize,
     },
 
-    /// Verify signature
+    /// Check that every commit in a range is signed by an allowed key
     Verify {
-        /// Signature (base64, or @file)
-        signature: String,
+        /// Commit range, e.g. origin/main..HEAD
+        range: String,
 
-        #[arg(short = 'k', long)]
-        pubkey: Option<String>,
+        /// allowed_signers file (default: gpg.ssh.allowedSignersFile)
+        #[arg(short, long)]
+        signers: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum WgAction {
+    /// Show WireGuard private key
+    Key {
+        /// Node name (default: realm)
+        #[arg(long)]
+        host: Option<String>,
+    },
+
+    /// Show WireGuard public key
+    Pub {
+        /// Node name (default: realm)
+        #[arg(long)]
+        host: Option<String>,
+    },
+
+    /// Show preshared key shared with a peer
+    Psk {
+        peer: String,
+
+        /// Node name (default: realm)
+        #[arg(long)]
+        host: Option<String>,
+    },
+
+    /// Render wg-quick config for a host from a peers manifest
+    Config {
+        #[arg(short, long)]
+        manifest: PathBuf,
+
+        #[arg(long)]
+        host: String,
+
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum PgpAction {
+    /// Show armored OpenPGP public key
+    Pub {
+        #[command(flatten)]
+        identity: PgpIdentity,
+    },
+
+    /// Show armored OpenPGP secret key (unprotected)
+    Key {
+        #[command(flatten)]
+        identity: PgpIdentity,
+    },
+
+    /// Show primary key fingerprint
+    Fingerprint {
+        /// Key creation time (unix seconds); part of the fingerprint
+        #[arg(long, default_value_t = pgp::DEFAULT_CREATED)]
+        created: u32,
+    },
+}
+
+#[derive(Args)]
+pub struct PgpIdentity {
+    /// User ID name
+    #[arg(long)]
+    name: Option<String>,
+
+    /// User ID email
+    #[arg(long)]
+    email: Option<String>,
+
+    /// Key creation time (unix seconds); part of the fingerprint
+    #[arg(long, default_value_t = pgp::DEFAULT_CREATED)]
+    created: u32,
+}
+
+impl PgpIdentity {
+    fn user_id(&self) -> Result<String, Box<dyn std::error::Error>> {
+        match (&self.name, &self.email) {
+            (Some(name), Some(email)) => Ok(format!("{name} <{email}>")),
+            (Some(name), None) => Ok(name.clone()),
+            (None, Some(email)) => Ok(format!("<{email}>")),
+            (None, None) => Err("pgp keys need a user ID: pass --name and/or --email".into()),
+        }
+    }
+}
+
+#[derive(Subcommand)]
+pub enum X509Action {
+    /// Show the realm's root CA certificate
+    Ca {
+        #[arg(long, value_enum, default_value = "ed25519")]
+        alg: KeyAlg,
+    },
+
+    /// Issue a leaf certificate signed by the realm CA
+    Issue {
+        /// Subject common name
+        #[arg(long)]
+        cn: String,
+
+        /// Subject alternative name, DNS name or IP (default: CN)
+        #[arg(long = "san", action = clap::ArgAction::Append)]
+        sans: Vec<String>,
+
+        /// Validity in days
+        #[arg(long, default_value_t = 365)]
+        days: u32,
+
+        #[arg(long, value_enum, default_value = "ed25519")]
+        alg: KeyAlg,
+
+        /// Also write the PKCS#8 key here (mode 0600)
+        #[arg(long)]
+        key_out: Option<PathBuf>,
+    },
+
+    /// Create a CSR for an external CA
+    Csr {
+        #[arg(long)]
+        cn: String,
+
+        #[arg(long = "san", action = clap::ArgAction::Append)]
+        sans: Vec<String>,
+
+        #[arg(long, value_enum, default_value = "ed25519")]
+        alg: KeyAlg,
+    },
+
+    /// Show PKCS#8 private key for CN (default: the CA key)
+    Key {
+        #[arg(long)]
+        cn: Option<String>,
+
+        #[arg(long, value_enum, default_value = "ed25519")]
+        alg: KeyAlg,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum KeyAlg {
+    Ed25519,
+    P256,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum KeyFormat {
+    /// Raw key bytes (Ed25519) or compressed point (P-256)
+    Base64,
+    /// SPKI public key or PKCS#8 private key
+    Pem,
+    Jwk,
+    /// minisign.pub (Ed25519 public key only)
+    Minisign,
+    /// signify .pub (Ed25519 public key only)
+    Signify,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum SignatureFormat {
+    /// Bare signature: base64, or bytes with --binary
+    Raw,
+    /// .minisig file with key ID and comments (Ed25519 only)
+    Minisign,
+    /// OpenBSD signify .sig file (Ed25519 only)
+    Signify,
+    /// JSON envelope with the key, namespace, time and comment, all signed
+    Envelope,
+}
+
+impl KeyFormat {
+    fn format(self) -> sign::KeyFormat {
+        match self {
+            KeyFormat::Base64 => sign::KeyFormat::Base64,
+            KeyFormat::Pem => sign::KeyFormat::Pem,
+            KeyFormat::Jwk => sign::KeyFormat::Jwk,
+            KeyFormat::Minisign | KeyFormat::Signify => {
+                unreachable!("minisign and signify keys are handled by the caller")
+            }
+        }
+    }
+}
+
+impl KeyAlg {
+    fn algorithm(self) -> ec::Algorithm {
+        match self {
+            KeyAlg::Ed25519 => ec::Algorithm::Ed25519,
+            KeyAlg::P256 => ec::Algorithm::P256,
+        }
+    }
+
+    fn ed25519_only(self, format: &str) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            KeyAlg::Ed25519 => Ok(()),
+            KeyAlg::P256 => Err(format!("{format} format supports only Ed25519 keys").into()),
+        }
+    }
+}
+
+#[derive(Subcommand)]
+pub enum NostrAction {
+    /// Show public key (npub)
+    Pub {
+        /// Print the hex key used in events instead
+        #[arg(long)]
+        hex: bool,
+    },
+
+    /// Show private key (nsec)
+    Key,
 
+    /// Sign an unsigned event (JSON), filling in pubkey, id and sig
+    Sign {
+        /// Event file (default: stdin)
         file: Option<PathBuf>,
     },
 }
 
 #[derive(Subcommand)]
-pub enum DeriveAction {
-    /// Derive password for site
-    Password {
-        site: String,
+pub enum JwtAction {
+    /// Sign a JWT (EdDSA) over JSON claims
+    Sign {
+        /// Claims file, a JSON object (default: stdin)
+        #[arg(long)]
+        claims: Option<PathBuf>,
 
-        #[arg(short, long, default_value = "16")]
-        length: usize,
+        /// Set exp this far after now, e.g. 1h or 7d
+        #[arg(long)]
+        exp: Option<String>,
+    },
+
+    /// Verify a JWT and print its claims
+    Verify {
+        /// Token (default: stdin)
+        token: Option<String>,
 
+        /// Verify against a JWKS document instead of the realm's key
         #[arg(long)]
-        no_symbols: bool,
+        jwks: Option<PathBuf>,
+    },
 
-        #[arg(long, default_value = "!@#$%^&*")]
-        symbols: String,
+    /// Show the JWKS document with the public key (each of --realms)
+    Jwks,
+}
 
-        #[arg(short = 'n', long, default_value = "1")]
-        counter: u32,
+#[derive(Subcommand)]
+pub enum PasetoAction {
+    /// Show the v4.public verification key (PASERK k4.public)
+    Pub,
+
+    /// Sign a message as a v4.public token
+    Sign {
+        /// Message file (default: stdin)
+        file: Option<PathBuf>,
+
+        #[command(flatten)]
+        extra: PasetoExtra,
     },
 
-    /// Derive BIP39 mnemonic
-    Mnemonic {
-        #[arg(short, long, default_value = "24")]
-        words: usize,
+    /// Verify a v4.public token and print its message
+    Verify {
+        /// Token (default: stdin)
+        token: Option<String>,
+
+        /// Verify against this key (k4.public) instead of the realm's key
+        #[arg(short = 'k', long)]
+        pubkey: Option<String>,
+
+        #[command(flatten)]
+        extra: PasetoExtra,
+    },
+
+    /// Encrypt a message as a v4.local token
+    Encrypt {
+        /// Message file (default: stdin)
+        file: Option<PathBuf>,
+
+        #[command(flatten)]
+        extra: PasetoExtra,
     },
 
-    /// Derive raw bytes
-    Raw {
-        path: String,
+    /// Decrypt a v4.local token and print its message
+    Decrypt {
+        /// Token (default: stdin)
+        token: Option<String>,
+
+        #[command(flatten)]
+        extra: PasetoExtra,
+    },
+}
+
+#[derive(Args)]
+pub struct PasetoExtra {
+    /// Footer: appended in the clear and authenticated; when verifying or
+    /// decrypting, the token's footer must match
+    #[arg(long)]
+    footer: Option<String>,
+
+    /// Implicit assertion: authenticated but not part of the token
+    #[arg(long, default_value = "")]
+    implicit: String,
+}
+
+#[derive(Subcommand)]
+pub enum SignAction {
+    /// Show signing public key
+    Pub {
+        #[arg(short = 't', long = "type", value_enum, default_value = "ed25519")]
+        alg: KeyAlg,
+
+        #[arg(long, value_enum, default_value = "base64")]
+        format: KeyFormat,
+    },
+
+    /// Show signing private key
+    Key {
+        #[arg(short = 't', long = "type", value_enum, default_value = "ed25519")]
+        alg: KeyAlg,
+
+        #[arg(long, value_enum, default_value = "pem")]
+        format: KeyFormat,
+    },
+
+    /// Sign data
+    Data {
+        #[arg(short = 't', long = "type", value_enum, default_value = "ed25519")]
+        alg: KeyAlg,
+
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        #[arg(long)]
+        binary: bool,
+
+        #[arg(long, value_enum, default_value = "raw")]
+        format: SignatureFormat,
+
+        /// Ed25519ph over a streamed SHA-512, for files too large to hold
+        /// in memory (raw; P-256 and envelopes always stream)
+        #[arg(long)]
+        prehash: bool,
+
+        /// What the signature is for, e.g. release or backup (envelope)
+        #[arg(short, long)]
+        namespace: Option<String>,
+
+        /// Trusted comment, signed with the data (minisign, envelope;
+        /// minisign default: timestamp and file name)
+        #[arg(long)]
+        trusted_comment: Option<String>,
+
+        /// Untrusted comment (minisign, signify)
+        #[arg(long, default_value = "signature from 1seed secret key")]
+        untrusted_comment: String,
+
+        file: Option<PathBuf>,
+    },
+
+    /// Verify signature
+    Verify {
+        /// Signature (base64, or @file; minisign, signify and envelope
+        /// files are recognized)
+        signature: String,
+
+        /// Public key in base64, or @file (default: derived, see --type)
+        #[arg(short = 'k', long)]
+        pubkey: Option<String>,
+
+        #[arg(short = 't', long = "type", value_enum, default_value = "ed25519")]
+        alg: KeyAlg,
+
+        /// Namespace the signature must be for (envelope)
+        #[arg(short, long)]
+        namespace: Option<String>,
+
+        /// Expect an Ed25519ph signature, as made by `sign data --prehash`
+        #[arg(long)]
+        prehash: bool,
+
+        file: Option<PathBuf>,
+    },
+}
+
+#[derive(Args)]
+pub struct Bip32Args {
+    /// Derivation path
+    #[arg(long, default_value = "m/84'/0'/0'")]
+    path: String,
+
+    /// Mnemonic length the wallet was created with
+    #[arg(short, long, default_value = "24")]
+    words: usize,
+
+    /// Mnemonic language the wallet was created with
+    #[arg(long, value_enum, default_value = "english")]
+    language: MnemonicLanguage,
+
+    /// Prompt for a BIP39 passphrase
+    #[arg(short, long)]
+    passphrase: bool,
+
+    /// SLIP-132 prefix (default: from the path's purpose and coin type)
+    #[arg(long, value_enum)]
+    format: Option<Slip132>,
+}
+
+impl Bip32Args {
+    fn derive(
+        &self,
+        seed: &Seed,
+        realm: &str,
+    ) -> Result<(bip32::ExtendedKey, bip32::Version), Box<dyn std::error::Error>> {
+        let path = bip32::parse_path(&self.path)?;
+        let passphrase = bip39_passphrase(self.passphrase)?;
+        let key = derive::extended_key(
+            seed,
+            realm,
+            self.words,
+            self.language.language(),
+            &passphrase,
+            &path,
+        )?;
+        let version = match self.format {
+            Some(format) => format.version(),
+            None => bip32::Version::for_path(&path),
+        };
+        Ok((key, version))
+    }
+}
+
+#[derive(Subcommand)]
+pub enum Bip85App {
+    /// Child BIP39 mnemonic
+    Mnemonic {
+        #[arg(short, long, default_value = "12")]
+        words: usize,
+
+        #[arg(long, value_enum, default_value = "english")]
+        language: MnemonicLanguage,
+
+        #[command(flatten)]
+        root: Bip85Root,
+    },
+
+    /// Hex entropy
+    Hex {
+        /// Byte length: 16-64
+        #[arg(short, long, default_value = "32")]
+        length: usize,
+
+        #[command(flatten)]
+        root: Bip85Root,
+    },
+
+    /// Base64 password
+    Pwd64 {
+        /// Password length: 20-86
+        #[arg(short, long, default_value = "20")]
+        length: usize,
+
+        #[command(flatten)]
+        root: Bip85Root,
+    },
+
+    /// Base85 password
+    Pwd85 {
+        /// Password length: 10-80
+        #[arg(short, long, default_value = "20")]
+        length: usize,
+
+        #[command(flatten)]
+        root: Bip85Root,
+    },
+}
+
+#[derive(Args)]
+pub struct Bip85Root {
+    /// Child index
+    #[arg(short, long, default_value = "0")]
+    index: u32,
+
+    /// Read the root xprv from stdin instead of using the realm's mnemonic
+    #[arg(long)]
+    root: bool,
+
+    /// Word count of the realm's mnemonic
+    #[arg(long, default_value = "24")]
+    root_words: usize,
+
+    /// Language of the realm's mnemonic
+    #[arg(long, value_enum, default_value = "english")]
+    root_language: MnemonicLanguage,
+
+    /// Prompt for the realm mnemonic's BIP39 passphrase
+    #[arg(short, long, conflicts_with = "root")]
+    passphrase: bool,
+}
+
+impl Bip85Root {
+    fn key(
+        &self,
+        cli: &Cli,
+        realm: &str,
+    ) -> Result<bip32::ExtendedKey, Box<dyn std::error::Error>> {
+        if self.root {
+            let input = harden::read_all(std::io::stdin())?;
+            let encoded =
+                std::str::from_utf8(&input).map_err(|_| "invalid extended private key")?;
+            return bip32::ExtendedKey::from_private_string(encoded);
+        }
+
+        let (seed, _) = get_seed(cli)?;
+        let passphrase = bip39_passphrase(self.passphrase)?;
+        derive::extended_key(
+            &seed,
+            realm,
+            self.root_words,
+            self.root_language.language(),
+            &passphrase,
+            &[],
+        )
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum MnemonicLanguage {
+    English,
+    ChineseSimplified,
+    ChineseTraditional,
+    Czech,
+    French,
+    Italian,
+    Japanese,
+    Korean,
+    Portuguese,
+    Spanish,
+}
+
+impl MnemonicLanguage {
+    fn language(self) -> bip39::Language {
+        match self {
+            MnemonicLanguage::English => bip39::Language::English,
+            MnemonicLanguage::ChineseSimplified => bip39::Language::SimplifiedChinese,
+            MnemonicLanguage::ChineseTraditional => bip39::Language::TraditionalChinese,
+            MnemonicLanguage::Czech => bip39::Language::Czech,
+            MnemonicLanguage::French => bip39::Language::French,
+            MnemonicLanguage::Italian => bip39::Language::Italian,
+            MnemonicLanguage::Japanese => bip39::Language::Japanese,
+            MnemonicLanguage::Korean => bip39::Language::Korean,
+            MnemonicLanguage::Portuguese => bip39::Language::Portuguese,
+            MnemonicLanguage::Spanish => bip39::Language::Spanish,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Slip132 {
+    /// xpub/xprv (P2PKH)
+    X,
+    /// ypub/yprv (P2WPKH in P2SH)
+    Y,
+    /// zpub/zprv (P2WPKH)
+    Z,
+    /// tpub/tprv (testnet)
+    T,
+    /// upub/uprv (testnet P2WPKH in P2SH)
+    U,
+    /// vpub/vprv (testnet P2WPKH)
+    V,
+}
+
+impl Slip132 {
+    fn version(self) -> bip32::Version {
+        match self {
+            Slip132::X => bip32::Version::X,
+            Slip132::Y => bip32::Version::Y,
+            Slip132::Z => bip32::Version::Z,
+            Slip132::T => bip32::Version::T,
+            Slip132::U => bip32::Version::U,
+            Slip132::V => bip32::Version::V,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+pub enum DeriveAction {
+    /// Derive password for site
+    Password {
+        site: String,
+
+        #[arg(short, long, default_value = "16")]
+        length: usize,
+
+        #[arg(long)]
+        no_symbols: bool,
+
+        #[arg(long, default_value = "!@#$%^&*")]
+        symbols: String,
+
+        #[arg(short = 'n', long, default_value = "1")]
+        counter: u32,
+    },
+
+    /// Derive BIP39 mnemonic
+    Mnemonic {
+        #[arg(short, long, default_value = "24")]
+        words: usize,
+
+        #[arg(long, value_enum, default_value = "english")]
+        language: MnemonicLanguage,
+
+        /// Prompt for a BIP39 passphrase (changes the fingerprint shown)
+        #[arg(short, long)]
+        passphrase: bool,
+
+        /// Read a mnemonic from stdin and check it against this realm
+        #[arg(long)]
+        verify: bool,
+    },
+
+    /// Derive BIP32 extended public key from the mnemonic (watch-only)
+    Xpub {
+        #[command(flatten)]
+        bip32: Bip32Args,
+    },
+
+    /// Derive BIP32 extended private key from the mnemonic
+    Xprv {
+        #[command(flatten)]
+        bip32: Bip32Args,
+    },
+
+    /// Derive BIP85 child mnemonics, entropy and passwords
+    Bip85 {
+        #[command(subcommand)]
+        app: Bip85App,
+    },
+
+    /// Derive raw bytes
+    Raw {
+        path: String,
+
+        #[arg(short, long, default_value = "32")]
+        length: usize,
+
+        #[arg(long)]
+        hex: bool,
+
+        #[arg(long)]
+        base64: bool,
+
+        #[arg(long)]
+        binary: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum OtpAction {
+    /// Show the otpauth:// enrollment URI for an account
+    Secret {
+        /// Account label, e.g. GitHub:alice@example.com
+        label: String,
+
+        /// Print only the base32 secret
+        #[arg(long, conflicts_with_all = ["digits", "period", "counter"])]
+        base32: bool,
+
+        #[arg(short, long, default_value = "6")]
+        digits: u32,
+
+        #[arg(long, default_value = "30")]
+        period: u64,
+
+        /// HOTP counter (enrolls as HOTP instead of TOTP)
+        #[arg(short = 'c', long)]
+        counter: Option<u64>,
+    },
+
+    /// Generate the current code for an account
+    Code {
+        /// Account label, e.g. GitHub:alice@example.com
+        label: String,
+
+        #[arg(short, long, default_value = "6")]
+        digits: u32,
+
+        #[arg(long, default_value = "30")]
+        period: u64,
+
+        /// HOTP counter (generates HOTP instead of TOTP)
+        #[arg(short = 'c', long)]
+        counter: Option<u64>,
+    },
+}
+
+impl Cli {
+    pub fn get_realm(&self) -> String {
+        self.realm.clone().unwrap_or_else(|| "default".to_string())
+    }
+
+    pub fn get_realms(&self) -> Vec<String> {
+        if !self.realms.is_empty() {
+            return self.realms.clone();
+        }
+
+        std::env::var("SEED_REALMS")
+            .map(|list| {
+                list.split(',')
+                    .map(str::trim)
+                    .filter(|r| !r.is_empty())
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl Commands {
+    /// Commands that honour `--realms`; every other command rejects it.
+    fn supports_realms(&self) -> bool {
+        matches!(
+            self,
+            Commands::Age {
+                action: AgeAction::Pub
+            } | Commands::Ssh {
+                action: SshAction::Pub { .. }
+                    | SshAction::Ca {
+                        action: SshCaAction::Pub
+                    }
+            } | Commands::Pgp {
+                action: PgpAction::Fingerprint { .. }
+            } | Commands::Nostr {
+                action: NostrAction::Pub { .. }
+            } | Commands::Jwt {
+                action: JwtAction::Jwks
+            } | Commands::Paseto {
+                action: PasetoAction::Pub
+            } | Commands::Sign {
+                action: SignAction::Pub { .. }
+            } | Commands::Status
+        )
+    }
+}
+
+fn get_seed(cli: &Cli) -> Result<(Seed, SeedSource), Box<dyn std::error::Error>> {
+    Seed::load(cli.allow_insecure)
+}
+
+fn print_per_realm(cli: &Cli, realm: &str, derive: impl Fn(&str) -> String) {
+    if cli.realms.is_empty() {
+        println!("{}", derive(realm));
+    } else {
+        for r in &cli.realms {
+            println!("{r}\t{}", derive(r));
+        }
+    }
+}
+
+fn print_realm_table(seed: &Seed, realms: &[String]) {
+    let width = realms.iter().map(|r| r.len()).max().unwrap_or(0).max(5);
+
+    println!("keys:");
+    println!("  {:<width$}  {:<62}  {:<50}  SIGN", "REALM", "AGE", "SSH");
+    for r in realms {
+        println!(
+            "  {:<width$}  {:<62}  {:<50}  {}",
+            r,
+            age::derive_recipient(seed, r),
+            ssh::fingerprint(seed, r),
+            sign::derive_public(seed, r)
+        );
+    }
+}
+
+/// Split `NAME=VALUE`; extensions may omit the value.
+fn split_option(
+    option: &str,
+    value_required: bool,
+) -> Result<(String, String), Box<dyn std::error::Error>> {
+    match option.split_once('=') {
+        Some((name, value)) if !name.is_empty() => Ok((name.to_string(), value.to_string())),
+        None if !value_required && !option.is_empty() => Ok((option.to_string(), String::new())),
+        _ => Err(format!("invalid option '{option}': expected NAME=VALUE").into()),
+    }
+}
+
+fn check_otp_params(digits: u32, period: u64) -> Result<(), Box<dyn std::error::Error>> {
+    if !(6..=8).contains(&digits) {
+        return Err("digits must be 6, 7, or 8".into());
+    }
+    if period == 0 {
+        return Err("period must be at least 1 second".into());
+    }
+    Ok(())
+}
+
+/// Optional BIP39 passphrase, typed twice since a typo silently selects
+/// another wallet.
+fn bip39_passphrase(prompt: bool) -> Result<Zeroizing<String>, Box<dyn std::error::Error>> {
+    if !prompt {
+        return Ok(Zeroizing::new(String::new()));
+    }
+    let pass = prompt_passphrase("BIP39 passphrase")?;
+    let confirm = prompt_passphrase("confirm")?;
+    if *pass != *confirm {
+        return Err("passphrases do not match".into());
+    }
+    Ok(pass)
+}
+
+fn prompt_passphrase(prompt: &str) -> Result<Zeroizing<String>, Box<dyn std::error::Error>> {
+    eprint!("{prompt}: ");
+    std::io::stderr().flush()?;
+    Ok(Zeroizing::new(rpassword::read_password()?))
+}
+
+/// Boolean environment variable, read the way clap reads the matching flags.
+fn env_flag(name: &str) -> bool {
+    std::env::var(name).is_ok_and(|value| {
+        !matches!(
+            value.to_ascii_lowercase().as_str(),
+            "" | "0" | "n" | "no" | "f" | "false" | "off"
+        )
+    })
+}
+
+/// `ssh-keygen -Y sign` arguments, as git passes them.
+struct KeygenSign {
+    key_file: String,
+    namespace: String,
+    files: Vec<String>,
+}
+
+fn parse_keygen_sign(args: &[std::ffi::OsString]) -> Option<KeygenSign> {
+    let mut sign = false;
+    let mut key_file = None;
+    let mut namespace = None;
+    let mut files = Vec::new();
+
+    let mut args = args.iter().map(|arg| arg.to_str());
+    while let Some(arg) = args.next() {
+        match arg? {
+            "-Y" => sign = args.next()?? == "sign",
+            "-f" => key_file = Some(args.next()??.to_string()),
+            "-n" => namespace = Some(args.next()??.to_string()),
+            // the key is always in a file for us, never in the agent
+            "-U" => {}
+            flag if flag.starts_with('-') && flag != "-" => return None,
+            file => files.push(file.to_string()),
+        }
+    }
+    if files.is_empty() {
+        files.push("-".to_string());
+    }
+
+    sign.then_some(KeygenSign {
+        key_file: key_file?,
+        namespace: namespace?,
+        files,
+    })
+}
+
+/// Stand-in for ssh-keygen as git's `gpg.ssh.program`, used when 1seed
+/// runs as `1seed-ssh-sign`. `-Y sign` with a key derived from the seed is
+/// answered here, so the private key never touches the disk; everything
+/// else (verification, other keys) is handed to ssh-keygen.
+pub fn run_ssh_keygen() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<std::ffi::OsString> = std::env::args_os().skip(1).collect();
+
+    if let Some(request) = parse_keygen_sign(&args) {
+        let key = std::fs::read_to_string(&request.key_file)
+            .ok()
+            .and_then(|key| ssh_key::PublicKey::from_openssh(key.trim()).ok());
+        if let Some(key) = key {
+            if env_flag("SEED_HARDEN") {
+                harden::enable()?;
+            }
+            let (seed, _) = Seed::load(env_flag("SEED_ALLOW_INSECURE"))?;
+
+            // the comment written by `ssh pub` names the realm
+            let realms = [
+                key.comment().strip_prefix("1seed:").map(str::to_string),
+                Some(std::env::var("SEED_REALM").unwrap_or_else(|_| "default".to_string())),
+            ];
+            let realm = realms
+                .into_iter()
+                .flatten()
+                .find(|realm| ssh::is_user_key(&seed, realm, &key));
+
+            if let Some(realm) = realm {
+                let key_type = ssh::key_type_of(&key).expect("a derived key type");
+                for file in &request.files {
+                    if file == "-" {
+                        let data = sign::read_input(None)?;
+                        let signature =
+                            ssh::sign_data(&seed, &realm, key_type, &request.namespace, &data)?;
+                        print!("{signature}");
+                    } else {
+                        let data = sign::read_input(Some(std::path::Path::new(file)))?;
+                        let signature =
+                            ssh::sign_data(&seed, &realm, key_type, &request.namespace, &data)?;
+                        std::fs::write(format!("{file}.sig"), signature)?;
+                    }
+                }
+                return Ok(());
+            }
+        }
+    }
+
+    let status = std::process::Command::new("ssh-keygen")
+        .args(&args)
+        .status()
+        .map_err(|e| format!("failed to run ssh-keygen: {e}"))?;
+    std::process::exit(status.code().unwrap_or(1));
+}
+
+pub fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+    let realm = cli.get_realm();
+
+    if !cli.realms.is_empty() && !cli.command.supports_realms() {
+        return Err("--realms is only supported by pub commands and status".into());
+    }
+
+    if cli.harden {
+        harden::enable()?;
+    }
+
+    match cli.command {
+        Commands::Age { ref action } => match action {
+            AgeAction::Pub => {
+                let (seed, _) = get_seed(&cli)?;
+                print_per_realm(&cli, &realm, |r| age::derive_recipient(&seed, r));
+            }
+
+            AgeAction::Key => {
+                let (seed, _) = get_seed(&cli)?;
+                println!("{}", age::derive_identity(&seed, &realm).as_str());
+            }
+
+            AgeAction::Encrypt {
+                ref recipients,
+                ref recipient_files,
+                self_,
+                passphrase,
+                armor,
+                ref output,
+                ref file,
+            } => {
+                if *passphrase {
+                    let pass = prompt_passphrase("passphrase")?;
+                    age::encrypt_passphrase(&pass, *armor, file.as_deref(), output.as_deref())?;
+                } else {
+                    let (seed, _) = get_seed(&cli)?;
+                    let use_self = *self_ || (recipients.is_empty() && recipient_files.is_empty());
+
+                    let mut all_recipients: Vec<Box<dyn ::age::Recipient + Send>> = vec![];
+
+                    if use_self {
+                        let recipient_str = age::derive_recipient(&seed, &realm);
+                        all_recipients.push(age::parse_recipient(&recipient_str)?);
+                    }
+
+                    for r in recipients {
+                        all_recipients.push(age::parse_recipient(r)?);
+                    }
+
+                    for f in recipient_files {
+                        all_recipients.extend(age::parse_recipients_file(f)?);
+                    }
+
+                    age::encrypt(all_recipients, *armor, file.as_deref(), output.as_deref())?;
+                }
+            }
+
+            AgeAction::Decrypt {
+                ref key,
+                passphrase,
+                ref output,
+                ref file,
+            } => {
+                if *passphrase {
+                    let pass = prompt_passphrase("passphrase")?;
+                    age::decrypt_passphrase(&pass, file.as_deref(), output.as_deref())?;
+                } else if let Some(key_file) = key {
+                    age::decrypt_with_file(key_file, file.as_deref(), output.as_deref())?;
+                } else {
+                    let (seed, _) = get_seed(&cli)?;
+                    let identity = age::derive_identity(&seed, &realm);
+                    age::decrypt(&identity, file.as_deref(), output.as_deref())?;
+                }
+            }
+        },
+
+        Commands::Ssh { ref action } => match action {
+            SshAction::Pub { key_type, bits } => {
+                let key_type = key_type.key_type(*bits)?;
+                let (seed, _) = get_seed(&cli)?;
+                print_per_realm(&cli, &realm, |r| ssh::derive_public(&seed, r, key_type));
+            }
+
+            SshAction::Key {
+                format,
+                key_type,
+                bits,
+            } => {
+                let key_type = key_type.key_type(*bits)?;
+                let (seed, _) = get_seed(&cli)?;
+                let key = match format {
+                    SshKeyFormat::Openssh => ssh::derive_private(&seed, &realm, key_type),
+                    SshKeyFormat::Pkcs8 => ssh::derive_private_pkcs8(&seed, &realm, key_type),
+                };
+                print!("{}", key.as_str());
+            }
+
+            SshAction::Add {
+                key_type,
+                bits,
+                lifetime,
+                confirm,
+            } => {
+                let key_type = key_type.key_type(*bits)?;
+                let (seed, _) = get_seed(&cli)?;
+                ssh::add_to_agent(&seed, &realm, key_type, *lifetime, *confirm)?;
+                eprintln!("added 1seed:{realm} to agent");
+            }
+
+            SshAction::Sign {
+                ref namespace,
+                key_type,
+                bits,
+                ref output,
+                ref file,
+            } => {
+                let key_type = key_type.key_type(*bits)?;
+                let data = sign::read_input(file.as_deref())?;
+                let (seed, _) = get_seed(&cli)?;
+                let signature = ssh::sign_data(&seed, &realm, key_type, namespace, &data)?;
+
+                match output {
+                    Some(path) => std::fs::write(path, signature)?,
+                    None => print!("{signature}"),
+                }
+            }
+
+            SshAction::Verify {
+                ref signature,
+                ref namespace,
+                ref allowed_signers,
+                ref identity,
+                ref file,
+            } => {
+                let signature = std::fs::read_to_string(signature)?;
+                let data = sign::read_input(file.as_deref())?;
+                let key = ssh::check_signature(&signature, namespace, &data)?;
+
+                let signer = match allowed_signers {
+                    Some(path) => ssh::find_signer(
+                        &std::fs::read_to_string(path)?,
+                        &key,
+                        namespace,
+                        identity.as_deref(),
+                    )?
+                    .ok_or("signing key is not in allowed_signers")?,
+                    None => {
+                        let (seed, _) = get_seed(&cli)?;
+                        if !ssh::is_user_key(&seed, &realm, &key) {
+                            return Err(format!("signing key is not realm '{realm}'").into());
+                        }
+                        format!("1seed:{realm}")
+                    }
+                };
+                println!(
+                    "Good \"{namespace}\" signature for {signer} with {} key {}",
+                    key.algorithm(),
+                    key.fingerprint(ssh_key::HashAlg::Sha256)
+                );
+            }
+
+            SshAction::Hostkey {
+                ref host,
+                ref names,
+                install,
+                ref dir,
+            } => {
+                let (seed, _) = get_seed(&cli)?;
+                if *install {
+                    ssh::install_hostkey(&seed, &realm, host, dir)?;
+                    eprintln!(
+                        "wrote {}",
+                        dir.join("ssh_host_ed25519_key{,.pub}").display()
+                    );
+                }
+                println!("{}", ssh::known_hosts_line(&seed, &realm, host, names)?);
+            }
+
+            SshAction::Ca { ref action } => match action {
+                SshCaAction::Pub => {
+                    let (seed, _) = get_seed(&cli)?;
+                    print_per_realm(&cli, &realm, |r| ssh::ca_public(&seed, r));
+                }
+
+                SshCaAction::Sign {
+                    ref file,
+                    ref principals,
+                    ref validity,
+                    host,
+                    serial,
+                    ref key_id,
+                    ref options,
+                    ref extensions,
+                    ref output,
+                } => {
+                    let public_key = match file {
+                        Some(path) => std::fs::read_to_string(path)?,
+                        None => std::io::read_to_string(std::io::stdin())?,
+                    };
+
+                    let now = std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)?
+                        .as_secs();
+                    let (valid_after, valid_before) = ssh::parse_validity(validity, now)?;
+
+                    let opts = ssh::CertOptions {
+                        principals: principals.clone(),
+                        host: *host,
+                        valid_after,
+                        valid_before,
+                        serial: *serial,
+                        key_id: key_id.clone().unwrap_or_else(|| principals.join(",")),
+                        critical_options: options
+                            .iter()
+                            .map(|o| split_option(o, true))
+                            .collect::<Result<_, _>>()?,
+                        extensions: if extensions.is_empty() {
+                            None
+                        } else {
+                            Some(
+                                extensions
+                                    .iter()
+                                    .map(|e| split_option(e, false))
+                                    .collect::<Result<_, _>>()?,
+                            )
+                        },
+                    };
+
+                    let (seed, _) = get_seed(&cli)?;
+                    let cert = ssh::sign_certificate(&seed, &realm, &public_key, &opts)?;
+                    if let Some(path) = output {
+                        std::fs::write(path, format!("{cert}\n"))?;
+                    } else {
+                        println!("{cert}");
+                    }
+                }
+            },
+        },
+
+        Commands::Git { ref action } => match action {
+            GitAction::Setup {
+                global,
+                ref email,
+                ref allowed_signers,
+                key_type,
+                bits,
+            } => {
+                let options = git::SetupOptions {
+                    global: *global,
+                    email: email.clone(),
+                    allowed_signers: allowed_signers.clone(),
+                    key_type: key_type.key_type(*bits)?,
+                };
+                let (seed, _) = get_seed(&cli)?;
+                let setup = git::setup(&seed, &realm, &options)?;
+
+                let scope = if *global { "global" } else { "repository" };
+                eprintln!("configured {scope} git to sign with 1seed:{realm}");
+                if setup.added {
+                    eprintln!(
+                        "added {} to {}",
+                        setup.email,
+                        setup.allowed_signers.display()
+                    );
+                } else {
+                    eprintln!(
+                        "{} already in {}",
+                        setup.email,
+                        setup.allowed_signers.display()
+                    );
+                }
+                println!("{}", setup.public_key);
+            }
+
+            GitAction::Verify {
+                ref range,
+                ref signers,
+            } => {
+                let signers = match signers {
+                    Some(path) => path.clone(),
+                    None => git::allowed_signers_file()?,
+                };
+                let allowed_signers = std::fs::read_to_string(&signers)
+                    .map_err(|e| format!("cannot read {}: {e}", signers.display()))?;
+                let checks = git::verify_range(range, &allowed_signers)?;
+
+                let failed = checks.iter().filter(|c| c.result.is_err()).count();
+                for check in &checks {
+                    let short = &check.commit[..check.commit.len().min(12)];
+                    match &check.result {
+                        Ok(signer) => println!("ok   {short} {signer}  {}", check.subject),
+                        Err(reason) => println!("FAIL {short} {reason}  {}", check.subject),
+                    }
+                }
+                if failed > 0 {
+                    return Err(format!(
+                        "{failed} of {} commits failed verification",
+                        checks.len()
+                    )
+                    .into());
+                }
+            }
+        },
+
+        Commands::Wg { ref action } => match action {
+            WgAction::Key { ref host } => {
+                let (seed, _) = get_seed(&cli)?;
+                let node = host.as_deref().unwrap_or(&realm);
+                println!("{}", wg::derive_private(&seed, &realm, node)?.as_str());
+            }
+
+            WgAction::Pub { ref host } => {
+                let (seed, _) = get_seed(&cli)?;
+                let node = host.as_deref().unwrap_or(&realm);
+                println!("{}", wg::derive_public(&seed, &realm, node)?);
+            }
+
+            WgAction::Psk { ref peer, ref host } => {
+                let (seed, _) = get_seed(&cli)?;
+                let node = host.as_deref().unwrap_or(&realm);
+                println!("{}", wg::derive_psk(&seed, &realm, node, peer)?.as_str());
+            }
+
+            WgAction::Config {
+                ref manifest,
+                ref host,
+                ref output,
+            } => {
+                let manifest = wg::Manifest::load(manifest)?;
+                let (seed, _) = get_seed(&cli)?;
+                let config = wg::render_config(&seed, &realm, &manifest, host)?;
+                if let Some(path) = output {
+                    seed::write_private(path, config.as_bytes())?;
+                } else {
+                    print!("{}", config.as_str());
+                }
+            }
+        },
+
+        Commands::Pgp { ref action } => match action {
+            PgpAction::Pub { ref identity } => {
+                let uid = identity.user_id()?;
+                let (seed, _) = get_seed(&cli)?;
+                print!(
+                    "{}",
+                    pgp::derive_public(&seed, &realm, &uid, identity.created)
+                );
+            }
 
-        #[arg(short, long, default_value = "32")]
-        length: usize,
+            PgpAction::Key { ref identity } => {
+                let uid = identity.user_id()?;
+                let (seed, _) = get_seed(&cli)?;
+                print!(
+                    "{}",
+                    pgp::derive_private(&seed, &realm, &uid, identity.created).as_str()
+                );
+            }
 
-        #[arg(long)]
-        hex: bool,
+            PgpAction::Fingerprint { created } => {
+                let (seed, _) = get_seed(&cli)?;
+                print_per_realm(&cli, &realm, |r| pgp::fingerprint(&seed, r, *created));
+            }
+        },
 
-        #[arg(long)]
-        base64: bool,
+        Commands::X509 { ref action } => match action {
+            X509Action::Ca { alg } => {
+                let (seed, _) = get_seed(&cli)?;
+                print!("{}", x509::ca_certificate(&seed, &realm, alg.algorithm())?);
+            }
 
-        #[arg(long)]
-        binary: bool,
-    },
-}
+            X509Action::Issue {
+                ref cn,
+                ref sans,
+                days,
+                alg,
+                ref key_out,
+            } => {
+                let (seed, _) = get_seed(&cli)?;
+                let cert = x509::issue(&seed, &realm, alg.algorithm(), cn, sans, *days)?;
+                if let Some(path) = key_out {
+                    let key = x509::private_key(&seed, &realm, alg.algorithm(), Some(cn))?;
+                    seed::write_private(path, key.as_bytes())?;
+                }
+                print!("{cert}");
+            }
 
-impl Cli {
-    pub fn get_realm(&self) -> String {
-        self.realm.clone().unwrap_or_else(|| "default".to_string())
-    }
-}
+            X509Action::Csr {
+                ref cn,
+                ref sans,
+                alg,
+            } => {
+                let (seed, _) = get_seed(&cli)?;
+                print!("{}", x509::csr(&seed, &realm, alg.algorithm(), cn, sans)?);
+            }
 
-fn get_seed(_cli: &Cli) -> Result<(Seed, SeedSource), Box<dyn std::error::Error>> {
-    Seed::load()
-}
+            X509Action::Key { ref cn, alg } => {
+                let (seed, _) = get_seed(&cli)?;
+                let key = x509::private_key(&seed, &realm, alg.algorithm(), cn.as_deref())?;
+                print!("{}", key.as_str());
+            }
+        },
 
-fn prompt_passphrase(prompt: &str) -> Result<String, Box<dyn std::error::Error>> {
-    eprint!("{prompt}: ");
-    std::io::stderr().flush()?;
-    Ok(rpassword::read_password()?)
-}
+        Commands::Nostr { ref action } => match action {
+            NostrAction::Pub { hex } => {
+                let (seed, _) = get_seed(&cli)?;
+                print_per_realm(&cli, &realm, |r| {
+                    if *hex {
+                        nostr::public_hex(&seed, r)
+                    } else {
+                        nostr::derive_public(&seed, r)
+                    }
+                });
+            }
 
-pub fn run() -> Result<(), Box<dyn std::error::Error>> {
-    let cli = Cli::parse();
-    let realm = cli.get_realm();
+            NostrAction::Key => {
+                let (seed, _) = get_seed(&cli)?;
+                println!("{}", nostr::derive_private(&seed, &realm).as_str());
+            }
 
-    match cli.command {
-        Commands::Age { ref action } => match action {
-            AgeAction::Pub => {
+            NostrAction::Sign { ref file } => {
+                let event = sign::read_input(file.as_deref())?;
                 let (seed, _) = get_seed(&cli)?;
-                println!("{}", age::derive_recipient(&seed, &realm));
+                println!("{}", nostr::sign_event(&seed, &realm, &event)?);
             }
+        },
 
-            AgeAction::Key => {
+        Commands::Jwt { ref action } => match action {
+            JwtAction::Sign {
+                ref claims,
+                ref exp,
+            } => {
+                let expires_in = exp.as_deref().map(ssh::parse_duration).transpose()?;
+                let claims = sign::read_input(claims.as_deref())?;
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)?
+                    .as_secs();
                 let (seed, _) = get_seed(&cli)?;
-                println!("{}", age::derive_identity(&seed, &realm));
+                println!("{}", jwt::sign(&seed, &realm, &claims, expires_in, now)?);
             }
 
-            AgeAction::Encrypt {
-                ref recipients,
-                ref recipient_files,
-                self_,
-                passphrase,
-                armor,
-                ref output,
-                ref file,
+            JwtAction::Verify {
+                ref token,
+                ref jwks,
             } => {
-                if *passphrase {
-                    let pass = prompt_passphrase("passphrase")?;
-                    age::encrypt_passphrase(&pass, *armor, file.as_deref(), output.as_deref())?;
+                let token = match token {
+                    Some(token) => token.clone(),
+                    None => std::io::read_to_string(std::io::stdin())?,
+                };
+                let keys = match jwks {
+                    Some(path) => jwt::parse_jwks(&std::fs::read(path)?)?,
+                    None => {
+                        let (seed, _) = get_seed(&cli)?;
+                        vec![jwt::Jwk::new(
+                            sign::signing_key(&seed, &realm).verifying_key(),
+                        )]
+                    }
+                };
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)?
+                    .as_secs();
+                let claims = jwt::verify(&token, &keys, now)?;
+                println!("{}", serde_json::to_string_pretty(&claims)?);
+            }
+
+            JwtAction::Jwks => {
+                let (seed, _) = get_seed(&cli)?;
+                let realms: Vec<&str> = if cli.realms.is_empty() {
+                    vec![&realm]
                 } else {
-                    let (seed, _) = get_seed(&cli)?;
-                    let use_self = *self_ || (recipients.is_empty() && recipient_files.is_empty());
+                    cli.realms.iter().map(String::as_str).collect()
+                };
+                println!("{}", jwt::jwks(&seed, &realms));
+            }
+        },
 
-                    let mut all_recipients: Vec<Box<dyn ::age::Recipient + Send>> = vec![];
+        Commands::Paseto { ref action } => match action {
+            PasetoAction::Pub => {
+                let (seed, _) = get_seed(&cli)?;
+                print_per_realm(&cli, &realm, |r| paseto::public_key(&seed, r));
+            }
 
-                    if use_self {
-                        let recipient_str = age::derive_recipient(&seed, &realm);
-                        all_recipients.push(age::parse_recipient(&recipient_str)?);
-                    }
+            PasetoAction::Sign {
+                ref file,
+                ref extra,
+            } => {
+                let message = sign::read_input(file.as_deref())?;
+                let footer = extra.footer.as_deref().unwrap_or_default();
+                let (seed, _) = get_seed(&cli)?;
+                println!(
+                    "{}",
+                    paseto::sign(
+                        &seed,
+                        &realm,
+                        &message,
+                        footer.as_bytes(),
+                        extra.implicit.as_bytes()
+                    )
+                );
+            }
 
-                    for r in recipients {
-                        all_recipients.push(age::parse_recipient(r)?);
+            PasetoAction::Verify {
+                ref token,
+                ref pubkey,
+                ref extra,
+            } => {
+                let token = match token {
+                    Some(token) => token.clone(),
+                    None => std::io::read_to_string(std::io::stdin())?,
+                };
+                let key = match pubkey {
+                    Some(paserk) => paseto::parse_public_key(paserk)?,
+                    None => {
+                        let (seed, _) = get_seed(&cli)?;
+                        sign::signing_key(&seed, &realm).verifying_key()
                     }
+                };
+                let message = paseto::verify(
+                    &token,
+                    &key,
+                    extra.footer.as_deref().map(str::as_bytes),
+                    extra.implicit.as_bytes(),
+                )?;
+                std::io::stdout().write_all(&message)?;
+            }
 
-                    for f in recipient_files {
-                        all_recipients.extend(age::parse_recipients_file(f)?);
+            PasetoAction::Encrypt {
+                ref file,
+                ref extra,
+            } => {
+                let message = match file {
+                    Some(path) => harden::read_all(std::fs::File::open(path)?)?,
+                    None => harden::read_all(std::io::stdin())?,
+                };
+                let footer = extra.footer.as_deref().unwrap_or_default();
+                let (seed, _) = get_seed(&cli)?;
+                println!(
+                    "{}",
+                    paseto::encrypt(
+                        &seed,
+                        &realm,
+                        &message,
+                        footer.as_bytes(),
+                        extra.implicit.as_bytes()
+                    )
+                );
+            }
+
+            PasetoAction::Decrypt {
+                ref token,
+                ref extra,
+            } => {
+                let token = match token {
+                    Some(token) => token.clone(),
+                    None => std::io::read_to_string(std::io::stdin())?,
+                };
+                let (seed, _) = get_seed(&cli)?;
+                let message = paseto::decrypt(
+                    &seed,
+                    &realm,
+                    &token,
+                    extra.footer.as_deref().map(str::as_bytes),
+                    extra.implicit.as_bytes(),
+                )?;
+                std::io::stdout().write_all(&message)?;
+            }
+        },
+
+        Commands::Sign { ref action } => match action {
+            SignAction::Pub { alg, format } => {
+                let (seed, _) = get_seed(&cli)?;
+                match format {
+                    KeyFormat::Minisign => {
+                        alg.ed25519_only("minisign")?;
+                        print_per_realm(&cli, &realm, |r| {
+                            minisign::public_key(&seed, r).trim_end().to_string()
+                        });
                     }
+                    KeyFormat::Signify => {
+                        alg.ed25519_only("signify")?;
+                        print_per_realm(&cli, &realm, |r| {
+                            signify::public_key(&seed, r).trim_end().to_string()
+                        });
+                    }
+                    _ => print_per_realm(&cli, &realm, |r| {
+                        let key = sign::public_key(&seed, r, alg.algorithm(), format.format());
+                        key.trim_end().to_string()
+                    }),
+                }
+            }
 
-                    age::encrypt(all_recipients, *armor, file.as_deref(), output.as_deref())?;
+            SignAction::Key { alg, format } => {
+                if let KeyFormat::Minisign | KeyFormat::Signify = format {
+                    return Err(
+                        "minisign and signify formats are only available for public keys".into(),
+                    );
                 }
+                let (seed, _) = get_seed(&cli)?;
+                let key = sign::private_key(&seed, &realm, alg.algorithm(), format.format())?;
+                println!("{}", key.trim_end());
             }
 
-            AgeAction::Decrypt {
-                ref key,
-                passphrase,
+            SignAction::Data {
+                alg,
                 ref output,
+                binary,
+                format: SignatureFormat::Minisign,
+                prehash,
+                ref namespace,
+                ref trusted_comment,
+                ref untrusted_comment,
                 ref file,
             } => {
-                if *passphrase {
-                    let pass = prompt_passphrase("passphrase")?;
-                    age::decrypt_passphrase(&pass, file.as_deref(), output.as_deref())?;
-                } else if let Some(key_file) = key {
-                    age::decrypt_with_file(key_file, file.as_deref(), output.as_deref())?;
-                } else {
-                    let (seed, _) = get_seed(&cli)?;
-                    let identity = age::derive_identity(&seed, &realm);
-                    age::decrypt(&identity, file.as_deref(), output.as_deref())?;
+                alg.ed25519_only("minisign")?;
+                if namespace.is_some() {
+                    return Err("--namespace is only used with --format envelope".into());
                 }
-            }
-        },
-
-        Commands::Ssh { ref action } => match action {
-            SshAction::Pub => {
+                if *prehash {
+                    return Err("--prehash is only used with --format raw".into());
+                }
+                if *binary {
+                    return Err("--binary cannot be used with --format minisign".into());
+                }
+                let trusted_comment = match trusted_comment {
+                    Some(comment) => comment.clone(),
+                    None => {
+                        let now = std::time::SystemTime::now()
+                            .duration_since(std::time::UNIX_EPOCH)?
+                            .as_secs();
+                        match file.as_deref().and_then(|path| path.file_name()) {
+                            Some(name) => {
+                                format!("timestamp:{now}\tfile:{}\thashed", name.to_string_lossy())
+                            }
+                            None => format!("timestamp:{now}\thashed"),
+                        }
+                    }
+                };
+                let data = sign::read_input(file.as_deref())?;
                 let (seed, _) = get_seed(&cli)?;
-                println!("{}", ssh::derive_public(&seed, &realm));
-            }
+                let signature =
+                    minisign::sign(&seed, &realm, &data, &trusted_comment, untrusted_comment)?;
 
-            SshAction::Key => {
-                let (seed, _) = get_seed(&cli)?;
-                print!("{}", ssh::derive_private(&seed, &realm));
+                match output {
+                    Some(path) => std::fs::write(path, signature)?,
+                    None => print!("{signature}"),
+                }
             }
 
-            SshAction::Add { lifetime, confirm } => {
+            SignAction::Data {
+                alg,
+                ref output,
+                binary,
+                format: SignatureFormat::Signify,
+                prehash,
+                ref namespace,
+                ref trusted_comment,
+                ref untrusted_comment,
+                ref file,
+            } => {
+                alg.ed25519_only("signify")?;
+                if namespace.is_some() {
+                    return Err("--namespace is only used with --format envelope".into());
+                }
+                if *prehash {
+                    return Err("--prehash is only used with --format raw".into());
+                }
+                if *binary {
+                    return Err("--binary cannot be used with --format signify".into());
+                }
+                if trusted_comment.is_some() {
+                    return Err("signify signatures have no trusted comment".into());
+                }
+                let data = sign::read_input(file.as_deref())?;
                 let (seed, _) = get_seed(&cli)?;
-                ssh::add_to_agent(&seed, &realm, *lifetime, *confirm)?;
-                eprintln!("added 1seed:{realm} to agent");
+                let signature = signify::sign(&seed, &realm, &data, untrusted_comment)?;
+
+                match output {
+                    Some(path) => std::fs::write(path, signature)?,
+                    None => print!("{signature}"),
+                }
             }
-        },
 
-        Commands::Sign { ref action } => match action {
-            SignAction::Pub => {
+            SignAction::Data {
+                alg,
+                ref output,
+                binary,
+                format: SignatureFormat::Envelope,
+                prehash,
+                ref namespace,
+                ref trusted_comment,
+                ref file,
+                ..
+            } => {
+                if *binary {
+                    return Err("--binary cannot be used with --format envelope".into());
+                }
+                if *prehash {
+                    return Err("--prehash is only used with --format raw".into());
+                }
+                let namespace = namespace
+                    .as_deref()
+                    .ok_or("--format envelope needs --namespace")?;
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)?
+                    .as_secs();
+                let digest = envelope::digest_input(file.as_deref())?;
                 let (seed, _) = get_seed(&cli)?;
-                println!("{}", sign::derive_public(&seed, &realm));
+                let envelope = envelope::sign(
+                    &seed,
+                    &realm,
+                    alg.algorithm(),
+                    namespace,
+                    trusted_comment.as_deref(),
+                    now,
+                    &digest,
+                )?;
+
+                match output {
+                    Some(path) => std::fs::write(path, envelope)?,
+                    None => print!("{envelope}"),
+                }
             }
 
             SignAction::Data {
+                alg,
                 ref output,
                 binary,
+                format: SignatureFormat::Raw,
+                prehash,
+                ref namespace,
+                ref trusted_comment,
                 ref file,
+                ..
             } => {
+                if namespace.is_some() || trusted_comment.is_some() {
+                    return Err(
+                        "raw signatures have no namespace or comment; use --format envelope".into(),
+                    );
+                }
                 let (seed, _) = get_seed(&cli)?;
-                let sig = sign::sign(&seed, &realm, file.as_deref())?;
+                let sig = sign::sign(&seed, &realm, alg.algorithm(), file.as_deref(), *prehash)?;
 
                 if *binary {
                     if let Some(path) = output {
@@ -363,6 +2140,9 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
             SignAction::Verify {
                 ref signature,
                 ref pubkey,
+                alg,
+                ref namespace,
+                prehash,
                 ref file,
             } => {
                 let sig_bytes = if let Some(path) = signature.strip_prefix('@') {
@@ -372,14 +2152,83 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
                     base64::engine::general_purpose::STANDARD.decode(signature)?
                 };
 
-                let pubkey_str = if let Some(pk) = pubkey.as_ref() {
-                    pk.clone()
-                } else {
+                let pubkey = match pubkey.as_ref() {
+                    Some(pk) => match pk.strip_prefix('@') {
+                        Some(path) => Some(std::fs::read_to_string(path)?),
+                        None => Some(pk.clone()),
+                    },
+                    None => None,
+                };
+
+                let derived_key = || -> Result<String, Box<dyn std::error::Error>> {
                     let (seed, _) = get_seed(&cli)?;
-                    sign::derive_public(&seed, &realm)
+                    Ok(sign::public_key(
+                        &seed,
+                        &realm,
+                        alg.algorithm(),
+                        sign::KeyFormat::Base64,
+                    ))
                 };
 
-                let valid = sign::verify(&pubkey_str, &sig_bytes, file.as_deref())?;
+                if *prehash
+                    && (envelope::is_signature(&sig_bytes) || signify::is_signature(&sig_bytes))
+                {
+                    return Err("--prehash is only used with raw signatures".into());
+                }
+
+                let valid = if envelope::is_signature(&sig_bytes) {
+                    let namespace = namespace
+                        .as_deref()
+                        .ok_or("envelope signatures need --namespace")?;
+                    let key = match pubkey {
+                        Some(pk) => pk,
+                        None => derived_key()?,
+                    };
+                    let digest = envelope::digest_input(file.as_deref())?;
+                    let signed = envelope::verify(&sig_bytes, &key, namespace, &digest)?;
+                    if let Some(signed) = &signed {
+                        let created = x509_cert::der::DateTime::from_unix_duration(
+                            std::time::Duration::from_secs(signed.created),
+                        )?;
+                        eprintln!("key: {} {}", signed.alg, signed.key);
+                        eprintln!("namespace: {}", signed.namespace);
+                        eprintln!("created: {created}");
+                        if let Some(comment) = &signed.comment {
+                            eprintln!("comment: {comment}");
+                        }
+                    }
+                    signed.is_some()
+                } else if namespace.is_some() {
+                    return Err("--namespace is only used with envelope signatures".into());
+                } else if signify::is_signature(&sig_bytes) {
+                    // minisign and signify share the public key format
+                    let key = match pubkey {
+                        Some(pk) => minisign::PublicKey::parse(&pk)?,
+                        None => {
+                            let (seed, _) = get_seed(&cli)?;
+                            minisign::PublicKey::new(
+                                sign::signing_key(&seed, &realm).verifying_key(),
+                            )
+                        }
+                    };
+                    let data = sign::read_input(file.as_deref())?;
+                    let signature = std::str::from_utf8(&sig_bytes)?;
+                    if minisign::is_signature(&sig_bytes) {
+                        let trusted_comment = minisign::verify(&key, signature, &data)?;
+                        if let Some(comment) = &trusted_comment {
+                            eprintln!("trusted comment: {comment}");
+                        }
+                        trusted_comment.is_some()
+                    } else {
+                        signify::verify(&key, signature, &data)?
+                    }
+                } else {
+                    let pubkey_str = match pubkey {
+                        Some(pk) => pk,
+                        None => derived_key()?,
+                    };
+                    sign::verify(pubkey_str.trim(), &sig_bytes, file.as_deref(), *prehash)?
+                };
 
                 if valid {
                     eprintln!("valid");
@@ -411,15 +2260,81 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
                 print!("{}", pw.as_str());
             }
 
-            DeriveAction::Mnemonic { words } => {
+            DeriveAction::Mnemonic {
+                words,
+                language,
+                passphrase,
+                verify,
+            } => {
+                let language = language.language();
+
+                if *verify {
+                    let input = harden::read_all(std::io::stdin())?;
+                    let phrase = std::str::from_utf8(&input).map_err(|_| "invalid mnemonic")?;
+                    let (seed, _) = get_seed(&cli)?;
+                    let words = derive::verify_mnemonic(&seed, &realm, phrase, language)?;
+
+                    let passphrase = bip39_passphrase(*passphrase)?;
+                    let root =
+                        derive::extended_key(&seed, &realm, words, language, &passphrase, &[])?;
+                    println!(
+                        "OK: mnemonic matches realm '{realm}' (fingerprint {})",
+                        hex::encode(root.fingerprint())
+                    );
+                    return Ok(());
+                }
+
                 eprintln!("WARNING: Cryptocurrency seed phrase");
                 eprintln!("  Same master seed = same mnemonic = same wallets");
                 eprintln!("  Compromise of master seed = loss of funds");
                 eprintln!("  Consider: dedicated realm, hardware wallet");
 
                 let (seed, _) = get_seed(&cli)?;
-                let mnemonic = derive::mnemonic(&seed, &realm, *words)?;
+                let mnemonic = derive::mnemonic(&seed, &realm, *words, language)?;
+                let passphrase = bip39_passphrase(*passphrase)?;
+                let root = derive::extended_key(&seed, &realm, *words, language, &passphrase, &[])?;
                 println!("{}", mnemonic.as_str());
+                eprintln!("Fingerprint: {}", hex::encode(root.fingerprint()));
+            }
+
+            DeriveAction::Xpub { ref bip32 } => {
+                let (seed, _) = get_seed(&cli)?;
+                let (key, version) = bip32.derive(&seed, &realm)?;
+                println!("{}", key.to_public_string(version));
+            }
+
+            DeriveAction::Xprv { ref bip32 } => {
+                eprintln!("WARNING: Cryptocurrency private key");
+                eprintln!("  Anyone holding this key can spend from the account");
+
+                let (seed, _) = get_seed(&cli)?;
+                let (key, version) = bip32.derive(&seed, &realm)?;
+                println!("{}", key.to_private_string(version).as_str());
+            }
+
+            DeriveAction::Bip85 { ref app } => {
+                let out = match app {
+                    Bip85App::Mnemonic {
+                        words,
+                        language,
+                        ref root,
+                    } => bip85::mnemonic(
+                        &root.key(&cli, &realm)?,
+                        *words,
+                        language.language(),
+                        root.index,
+                    )?,
+                    Bip85App::Hex { length, ref root } => {
+                        bip85::hex(&root.key(&cli, &realm)?, *length, root.index)?
+                    }
+                    Bip85App::Pwd64 { length, ref root } => {
+                        bip85::pwd64(&root.key(&cli, &realm)?, *length, root.index)?
+                    }
+                    Bip85App::Pwd85 { length, ref root } => {
+                        bip85::pwd85(&root.key(&cli, &realm)?, *length, root.index)?
+                    }
+                };
+                println!("{}", out.as_str());
             }
 
             DeriveAction::Raw {
@@ -446,41 +2361,77 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
             }
         },
 
+        Commands::Otp { ref action } => match action {
+            OtpAction::Secret {
+                ref label,
+                base32,
+                digits,
+                period,
+                counter,
+            } => {
+                check_otp_params(*digits, *period)?;
+                let (seed, _) = get_seed(&cli)?;
+                if *base32 {
+                    println!("{}", otp::secret_base32(&seed, &realm, label).as_str());
+                } else {
+                    let uri = otp::uri(&seed, &realm, label, *digits, *period, *counter);
+                    println!("{}", uri.as_str());
+                }
+            }
+
+            OtpAction::Code {
+                ref label,
+                digits,
+                period,
+                counter,
+            } => {
+                check_otp_params(*digits, *period)?;
+                let (seed, _) = get_seed(&cli)?;
+                let secret = otp::derive_secret(&seed, &realm, label);
+                let code = match counter {
+                    Some(c) => otp::hotp(&secret, *c, *digits),
+                    None => {
+                        let now = std::time::SystemTime::now()
+                            .duration_since(std::time::UNIX_EPOCH)?
+                            .as_secs();
+                        otp::totp(&secret, now, *period, *digits)
+                    }
+                };
+                println!("{code}");
+            }
+        },
+
         Commands::Init {
             passphrase,
             generate,
-            from_file,
+            ref from_file,
         } => {
             if Seed::exists() {
                 return Err("seed already exists, run '1seed forget --confirm' first".into());
             }
 
             let seed_data = if generate {
-                let mut bytes = [0u8; 32];
+                let mut bytes = Zeroizing::new(vec![0u8; 32]);
                 use std::fs::File;
                 use std::io::Read;
                 File::open("/dev/urandom")?.read_exact(&mut bytes)?;
-                bytes.to_vec()
+                bytes
             } else if let Some(path) = from_file {
-                std::fs::read(path)?
+                harden::read_all(std::fs::File::open(path)?)?
             } else if passphrase {
-                eprint!("passphrase: ");
-                std::io::stderr().flush()?;
-                let pass = rpassword::read_password()?;
-                eprint!("confirm: ");
-                std::io::stderr().flush()?;
-                let confirm = rpassword::read_password()?;
+                let pass = prompt_passphrase("passphrase")?;
+                let confirm = prompt_passphrase("confirm")?;
                 if pass != confirm {
                     return Err("passphrases do not match".into());
                 }
-                pass.into_bytes()
+                Zeroizing::new(pass.as_bytes().to_vec())
             } else {
                 return Err("must specify --passphrase, --generate, or --from-file".into());
             };
 
             Seed::store(&seed_data)?;
 
-            match Seed::load() {
+            match get_seed(&cli) {
                 Ok((_, source)) => {
                     let location = match source {
                         SeedSource::Keyring => "keyring",
@@ -495,6 +2446,38 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
             }
         }
 
+        Commands::Seed { ref action } => match action {
+            SeedAction::Move { to } => {
+                let target = match to {
+                    MoveTarget::Keyring => Backend::Keyring,
+                    MoveTarget::File => Backend::File,
+                    MoveTarget::EncryptedFile => Backend::EncryptedFile,
+                };
+
+                let passphrase = if target == Backend::EncryptedFile {
+                    let pass = prompt_passphrase("new passphrase")?;
+                    let confirm = prompt_passphrase("confirm")?;
+                    if pass != confirm {
+                        return Err("passphrases do not match".into());
+                    }
+                    Some(pass)
+                } else {
+                    None
+                };
+
+                let (from, fingerprint) = Seed::move_to(
+                    target,
+                    passphrase.as_deref().map(String::as_str),
+                    cli.allow_insecure,
+                )?;
+                eprintln!(
+                    "moved seed {fingerprint} from {} to {}",
+                    from.name(),
+                    target.name()
+                );
+            }
+        },
+
         Commands::Forget { confirm } => {
             if !confirm {
                 return Err("use --confirm to remove seed".into());
@@ -504,20 +2487,43 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
             eprintln!("seed removed");
         }
 
+        Commands::FixPerms => {
+            let fixed = Seed::fix_permissions()?;
+            if fixed.is_empty() {
+                eprintln!("seed file permissions already ok");
+            }
+            for path in fixed {
+                eprintln!("set {} to 0600", path.display());
+            }
+        }
+
+        Commands::Doctor { online } => {
+            doctor::run(online, cli.allow_insecure)?;
+        }
+
         Commands::Update { check } => {
             update::update(check)?;
         }
 
         Commands::Status => {
             println!("1seed {}", env!("CARGO_PKG_VERSION"));
-            let realm_info = if std::env::var("SEED_REALM").is_ok() {
+            let realms = cli.get_realms();
+            let realm_info = if !cli.realms.is_empty() {
+                format!("{} (--realms)", realms.join(", "))
+            } else if !realms.is_empty() {
+                format!("{} (SEED_REALMS)", realms.join(", "))
+            } else if std::env::var("SEED_REALM").is_ok() {
                 format!("{} (SEED_REALM)", realm)
             } else if cli.realm.is_some() {
                 format!("{} (--realm)", realm)
             } else {
                 realm.clone()
             };
-            println!("realm: {}", realm_info);
+            if realms.is_empty() {
+                println!("realm: {}", realm_info);
+            } else {
+                println!("realms: {}", realm_info);
+            }
 
             match get_seed(&cli) {
                 Ok((seed, source)) => {
@@ -525,12 +2531,22 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
                         SeedSource::EnvFile(ref path) => format!("SEED_FILE: {}", path.display()),
                         SeedSource::Keyring => "keyring".to_string(),
                         SeedSource::DefaultFile(ref path) => format!("{}", path.display()),
+                        SeedSource::EncryptedFile(ref path) => {
+                            format!("{} (encrypted)", path.display())
+                        }
                     };
                     println!("seed: {}", source_desc);
+                    println!("fingerprint: {}", seed.fingerprint());
+
+                    if !realms.is_empty() {
+                        print_realm_table(&seed, &realms);
+                        return Ok(());
+                    }
+
                     println!("keys:");
                     println!("  age:  {}", age::derive_recipient(&seed, &realm));
 
-                    let ssh_pub = ssh::derive_public(&seed, &realm);
+                    let ssh_pub = ssh::derive_public(&seed, &realm, ssh::KeyType::Ed25519);
                     let parts: Vec<&str> = ssh_pub.split_whitespace().collect();
                     if parts.len() >= 2 {
                         let key_preview = if parts[1].len() > 20 {
@@ -543,9 +2559,13 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
 
                     print!("  sign: {}", sign::derive_public(&seed, &realm));
                 }
-                Err(_) => {
+                Err(_) if !Seed::exists() => {
                     print!("seed: none");
                 }
+                Err(e) => {
+                    println!("seed: error: {e}");
+                    println!("run '1seed doctor' for details (--allow-insecure loads seed files others can read)");
+                }
             }
         }
     }
@@ -1,4 +1,6 @@
+use crate::bip32;
 use crate::seed::Seed;
+use bip39::{Language, Mnemonic};
 use zeroize::Zeroizing;
 
 pub fn raw(seed: &Seed, realm: &str, path: &str, length: usize) -> Zeroizing<Vec<u8>> {
@@ -6,11 +8,11 @@ pub fn raw(seed: &Seed, realm: &str, path: &str, length: usize) -> Zeroizing<Vec
     seed.derive(realm, &key_type, length)
 }
 
-pub fn mnemonic(
+fn mnemonic_entropy(
     seed: &Seed,
     realm: &str,
     words: usize,
-) -> Result<Zeroizing<String>, Box<dyn std::error::Error>> {
+) -> Result<Zeroizing<Vec<u8>>, Box<dyn std::error::Error>> {
     let entropy_bytes = match words {
         12 => 16, // 128 bits
         15 => 20, // 160 bits
@@ -20,10 +22,75 @@ pub fn mnemonic(
         _ => return Err("word count must be 12, 15, 18, 21, or 24".into()),
     };
 
-    let entropy = seed.derive(realm, "mnemonic", entropy_bytes);
-    let mnemonic = bip39::Mnemonic::from_entropy(&entropy)?;
+    Ok(seed.derive(realm, "mnemonic", entropy_bytes))
+}
+
+/// The realm's mnemonic. The words depend on the language but the entropy
+/// does not, so every language shows the same underlying secret.
+pub fn mnemonic(
+    seed: &Seed,
+    realm: &str,
+    words: usize,
+    language: Language,
+) -> Result<Zeroizing<String>, Box<dyn std::error::Error>> {
+    let entropy = mnemonic_entropy(seed, realm, words)?;
+    let mnemonic = Mnemonic::from_entropy_in(language, &entropy)?;
+
+    Ok(phrase(&mnemonic))
+}
+
+/// Words joined for display; Japanese uses the ideographic space as BIP39
+/// recommends.
+pub fn phrase(mnemonic: &Mnemonic) -> Zeroizing<String> {
+    let separator = match mnemonic.language() {
+        Language::Japanese => "\u{3000}",
+        _ => " ",
+    };
+    let mut out = Zeroizing::new(String::with_capacity(256));
+    for (i, word) in mnemonic.words().enumerate() {
+        if i > 0 {
+            out.push_str(separator);
+        }
+        out.push_str(word);
+    }
+    out
+}
+
+/// Check a written-down mnemonic against the realm's, returning its word
+/// count.
+pub fn verify_mnemonic(
+    seed: &Seed,
+    realm: &str,
+    phrase: &str,
+    language: Language,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let mnemonic =
+        Mnemonic::parse_in(language, phrase).map_err(|e| format!("invalid mnemonic: {e}"))?;
+    let words = mnemonic.word_count();
+    let (entropy, len) = mnemonic.to_entropy_array();
+    let entropy = Zeroizing::new(entropy);
 
-    Ok(Zeroizing::new(mnemonic.to_string()))
+    if *mnemonic_entropy(seed, realm, words)? != entropy[..len] {
+        return Err(format!("mnemonic does not match realm '{realm}'").into());
+    }
+    Ok(words)
+}
+
+/// BIP32 key at `path` under the realm's mnemonic, as a wallet importing
+/// that mnemonic (and BIP39 passphrase) would compute it.
+pub fn extended_key(
+    seed: &Seed,
+    realm: &str,
+    words: usize,
+    language: Language,
+    passphrase: &str,
+    path: &[u32],
+) -> Result<bip32::ExtendedKey, Box<dyn std::error::Error>> {
+    let entropy = mnemonic_entropy(seed, realm, words)?;
+    let mnemonic = Mnemonic::from_entropy_in(language, &entropy)?;
+    let bip39_seed = Zeroizing::new(mnemonic.to_seed(passphrase));
+
+    bip32::ExtendedKey::master(&*bip39_seed)?.derive_path(path)
 }
 
 #[cfg(test)]
@@ -34,8 +101,8 @@ mod tests {
     fn mnemonic_deterministic() {
         let seed = Seed::from_passphrase("test").unwrap();
 
-        let m1 = mnemonic(&seed, "realm", 24).unwrap();
-        let m2 = mnemonic(&seed, "realm", 24).unwrap();
+        let m1 = mnemonic(&seed, "realm", 24, Language::English).unwrap();
+        let m2 = mnemonic(&seed, "realm", 24, Language::English).unwrap();
 
         assert_eq!(m1.as_str(), m2.as_str());
     }
@@ -45,8 +112,64 @@ mod tests {
         let seed = Seed::from_passphrase("test").unwrap();
 
         for words in [12, 15, 18, 21, 24] {
-            let m = mnemonic(&seed, "realm", words).unwrap();
+            let m = mnemonic(&seed, "realm", words, Language::English).unwrap();
             assert_eq!(m.split_whitespace().count(), words);
         }
     }
+
+    #[test]
+    fn extended_key_matches_mnemonic() {
+        let seed = Seed::from_passphrase("test").unwrap();
+        let path = bip32::parse_path("m/84'/0'/0'").unwrap();
+
+        let m = Mnemonic::parse(
+            mnemonic(&seed, "realm", 12, Language::English)
+                .unwrap()
+                .as_str(),
+        )
+        .unwrap();
+        let expected = bip32::ExtendedKey::master(&m.to_seed("extra"))
+            .unwrap()
+            .derive_path(&path)
+            .unwrap();
+        let key = extended_key(&seed, "realm", 12, Language::English, "extra", &path).unwrap();
+        let no_passphrase = extended_key(&seed, "realm", 12, Language::English, "", &path).unwrap();
+
+        let version = bip32::Version::Z;
+        assert_eq!(
+            key.to_public_string(version),
+            expected.to_public_string(version)
+        );
+        assert_ne!(
+            key.to_public_string(version),
+            no_passphrase.to_public_string(version)
+        );
+    }
+
+    #[test]
+    fn languages_share_entropy() {
+        let seed = Seed::from_passphrase("test").unwrap();
+
+        let english = mnemonic(&seed, "realm", 12, Language::English).unwrap();
+        let japanese = mnemonic(&seed, "realm", 12, Language::Japanese).unwrap();
+        let english = Mnemonic::parse_in(Language::English, english.as_str()).unwrap();
+        let japanese = Mnemonic::parse_in(Language::Japanese, japanese.as_str()).unwrap();
+
+        assert_eq!(english.to_entropy(), japanese.to_entropy());
+        assert_eq!(phrase(&japanese).split('\u{3000}').count(), 12);
+    }
+
+    #[test]
+    fn verify_written_mnemonic() {
+        let seed = Seed::from_passphrase("test").unwrap();
+        let written = mnemonic(&seed, "realm", 18, Language::French).unwrap();
+        let messy = format!("  {}\n", written.replace(' ', "   "));
+
+        assert_eq!(
+            verify_mnemonic(&seed, "realm", &messy, Language::French).unwrap(),
+            18
+        );
+        assert!(verify_mnemonic(&seed, "other", &written, Language::French).is_err());
+        assert!(verify_mnemonic(&seed, "realm", &written, Language::English).is_err());
+    }
 }
@@ -0,0 +1,223 @@
+use crate::seed::Seed;
+use crate::update;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+#[derive(PartialEq)]
+enum Level {
+    Ok,
+    Warn,
+    Fail,
+}
+
+struct Report {
+    failures: usize,
+}
+
+impl Report {
+    fn ok(&mut self, check: &str, detail: impl AsRef<str>) {
+        self.print(Level::Ok, check, detail.as_ref());
+    }
+
+    fn warn(&mut self, check: &str, detail: impl AsRef<str>) {
+        self.print(Level::Warn, check, detail.as_ref());
+    }
+
+    fn fail(&mut self, check: &str, detail: impl AsRef<str>) {
+        self.print(Level::Fail, check, detail.as_ref());
+    }
+
+    fn print(&mut self, level: Level, check: &str, detail: &str) {
+        let tag = match level {
+            Level::Ok => "ok  ",
+            Level::Warn => "warn",
+            Level::Fail => "FAIL",
+        };
+        if level == Level::Fail {
+            self.failures += 1;
+        }
+        println!("{tag}  {check}: {detail}");
+    }
+}
+
+pub fn run(online: bool, allow_insecure: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let mut report = Report { failures: 0 };
+
+    check_env(&mut report);
+    check_seed_file(&mut report);
+    check_keyring(&mut report);
+    check_default_file(&mut report);
+    check_load(&mut report, allow_insecure);
+    check_agent(&mut report);
+    check_update(&mut report, online);
+
+    match report.failures {
+        0 => Ok(()),
+        1 => Err("1 problem found".into()),
+        n => Err(format!("{n} problems found").into()),
+    }
+}
+
+fn check_env(report: &mut Report) {
+    if let Ok(realm) = std::env::var("SEED_REALM") {
+        match realm_problem(&realm) {
+            Some(problem) => report.fail("SEED_REALM", format!("{realm:?} {problem}")),
+            None => report.ok("SEED_REALM", &realm),
+        }
+    }
+
+    if let Ok(list) = std::env::var("SEED_REALMS") {
+        let realms: Vec<&str> = list.split(',').map(str::trim).collect();
+        let bad: Vec<String> = realms
+            .iter()
+            .filter_map(|r| realm_problem(r).map(|p| format!("{r:?} {p}")))
+            .collect();
+        if bad.is_empty() {
+            report.ok("SEED_REALMS", realms.join(", "));
+        } else {
+            report.fail("SEED_REALMS", bad.join("; "));
+        }
+    }
+}
+
+fn realm_problem(realm: &str) -> Option<&'static str> {
+    if realm.is_empty() {
+        Some("is empty")
+    } else if realm.contains('/') {
+        Some("contains '/', which overlaps other derivation paths")
+    } else if realm.trim() != realm {
+        Some("has leading or trailing whitespace")
+    } else {
+        None
+    }
+}
+
+fn check_seed_file(report: &mut Report) {
+    let Ok(path) = std::env::var("SEED_FILE") else {
+        return;
+    };
+    let path = PathBuf::from(path);
+
+    if !path.exists() {
+        report.fail(
+            "SEED_FILE",
+            format!(
+                "{} does not exist, unset SEED_FILE or fix the path",
+                path.display()
+            ),
+        );
+        return;
+    }
+
+    check_file_mode(report, "SEED_FILE", &path);
+}
+
+fn check_keyring(report: &mut Report) {
+    if std::env::var("SEED_NO_KEYRING").is_ok() {
+        report.ok("keyring", "skipped (SEED_NO_KEYRING)");
+        return;
+    }
+
+    match Seed::keyring_probe() {
+        Ok(true) => report.ok("keyring", "seed present"),
+        Ok(false) => report.ok("keyring", "available, no seed stored"),
+        Err(e) => report.warn(
+            "keyring",
+            format!("unavailable ({e}), falling back to ~/.1seed"),
+        ),
+    }
+}
+
+fn check_default_file(report: &mut Report) {
+    for (check, path) in [
+        ("~/.1seed", Seed::default_file_path()),
+        ("~/.1seed.age", Seed::encrypted_file_path()),
+    ] {
+        if path.exists() {
+            check_file_mode(report, check, &path);
+        } else {
+            report.ok(check, "not present");
+        }
+    }
+}
+
+fn check_file_mode(report: &mut Report, check: &str, path: &Path) {
+    match Seed::check_permissions(path) {
+        Ok(()) => report.ok(check, path.display().to_string()),
+        Err(e) => report.fail(check, e),
+    }
+}
+
+fn check_load(report: &mut Report, allow_insecure: bool) {
+    match Seed::load(allow_insecure) {
+        Ok(_) => report.ok("seed", "loads"),
+        Err(e) => report.fail("seed", e.to_string()),
+    }
+}
+
+fn check_agent(report: &mut Report) {
+    let on_path = std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).any(|dir| dir.join("ssh-add").is_file()))
+        .unwrap_or(false);
+
+    if !on_path {
+        report.warn("ssh-add", "not found on PATH, 'ssh add' will fail");
+        return;
+    }
+    report.ok("ssh-add", "found");
+
+    let Some(sock) = std::env::var_os("SSH_AUTH_SOCK") else {
+        report.warn("ssh agent", "SSH_AUTH_SOCK not set, start ssh-agent first");
+        return;
+    };
+
+    if !Path::new(&sock).exists() {
+        report.warn(
+            "ssh agent",
+            format!(
+                "SSH_AUTH_SOCK points to missing {}",
+                Path::new(&sock).display()
+            ),
+        );
+        return;
+    }
+
+    // ssh-add -l exits 0 with keys, 1 with no keys, 2 when the agent is unreachable
+    let status = Command::new("ssh-add")
+        .arg("-l")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+
+    match status.map(|s| s.code()) {
+        Ok(Some(0)) | Ok(Some(1)) => report.ok("ssh agent", "reachable"),
+        _ => report.warn("ssh agent", "not reachable via SSH_AUTH_SOCK"),
+    }
+}
+
+fn check_update(report: &mut Report, online: bool) {
+    match std::env::current_exe() {
+        Ok(path) if update::is_writable(&path) => report.ok("update", "binary is writable"),
+        Ok(path) => report.warn(
+            "update",
+            format!("{} not writable, 'update' needs sudo", path.display()),
+        ),
+        Err(e) => report.warn("update", e.to_string()),
+    }
+
+    if !online {
+        return;
+    }
+
+    let current = env!("CARGO_PKG_VERSION");
+    match update::fetch_latest_version() {
+        Ok(latest) if latest.trim_start_matches('v') == current => {
+            report.ok("version", format!("v{current} is latest"))
+        }
+        Ok(latest) => report.warn(
+            "version",
+            format!("v{current} installed, {latest} available, run: 1seed update"),
+        ),
+        Err(e) => report.warn("version", format!("could not check: {e}")),
+    }
+}
@@ -0,0 +1,100 @@
+use crate::seed::Seed;
+use base64::Engine;
+use zeroize::Zeroizing;
+
+/// Curve for keys that come in more than one flavour (ssh, sign, x509).
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    Ed25519,
+    P256,
+}
+
+impl Algorithm {
+    pub fn name(self) -> &'static str {
+        match self {
+            Algorithm::Ed25519 => "ed25519",
+            Algorithm::P256 => "p256",
+        }
+    }
+}
+
+/// P-256 scalar by rejection sampling: out-of-range candidates (about 2^-32
+/// of them) move on to `label/1`, `label/2`, ...
+pub fn derive_p256(seed: &Seed, realm: &str, label: &str) -> p256::ecdsa::SigningKey {
+    let mut attempt = 0u32;
+    loop {
+        let candidate = if attempt == 0 {
+            seed.derive_32(realm, label)
+        } else {
+            seed.derive_32(realm, &format!("{label}/{attempt}"))
+        };
+        if let Ok(key) = p256::ecdsa::SigningKey::from_bytes((&*candidate).into()) {
+            return key;
+        }
+        attempt += 1;
+    }
+}
+
+fn b64url(data: &[u8]) -> String {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(data)
+}
+
+/// RFC 8037 OKP key; `d` is included only for private keys.
+pub fn ed25519_jwk(key: &ed25519_dalek::SigningKey, private: bool) -> Zeroizing<String> {
+    let mut jwk = serde_json::json!({
+        "kty": "OKP",
+        "crv": "Ed25519",
+        "x": b64url(key.verifying_key().as_bytes()),
+    });
+    if private {
+        jwk["d"] = b64url(key.as_bytes()).into();
+    }
+    Zeroizing::new(jwk.to_string())
+}
+
+/// RFC 7518 EC key; `d` is included only for private keys.
+pub fn p256_jwk(key: &p256::ecdsa::SigningKey, private: bool) -> Zeroizing<String> {
+    let point = key.verifying_key().to_encoded_point(false);
+    let mut jwk = serde_json::json!({
+        "kty": "EC",
+        "crv": "P-256",
+        "x": b64url(point.x().expect("uncompressed point")),
+        "y": b64url(point.y().expect("uncompressed point")),
+    });
+    if private {
+        jwk["d"] = b64url(&key.to_bytes()).into();
+    }
+    Zeroizing::new(jwk.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn p256_labels_independent() {
+        let seed = Seed::from_passphrase("test").unwrap();
+
+        let a = derive_p256(&seed, "realm", "a");
+        let a2 = derive_p256(&seed, "realm", "a");
+        let b = derive_p256(&seed, "realm", "b");
+
+        assert_eq!(a.to_bytes(), a2.to_bytes());
+        assert_ne!(a.to_bytes(), b.to_bytes());
+    }
+
+    #[test]
+    fn p256_jwk_coordinates() {
+        let seed = Seed::from_passphrase("test").unwrap();
+        let key = derive_p256(&seed, "realm", "a");
+
+        let public: serde_json::Value = serde_json::from_str(&p256_jwk(&key, false)).unwrap();
+        let private: serde_json::Value = serde_json::from_str(&p256_jwk(&key, true)).unwrap();
+
+        assert_eq!(public["crv"], "P-256");
+        assert_eq!(public["x"].as_str().unwrap().len(), 43);
+        assert!(public.get("d").is_none());
+        assert_eq!(private["x"], public["x"]);
+        assert_eq!(private["d"].as_str().unwrap().len(), 43);
+    }
+}
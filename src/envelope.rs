@@ -0,0 +1,207 @@
+//! Signature envelopes: a JSON document carrying the signer's public key,
+//! a namespace, the signing time, an optional comment and the SHA-512 of
+//! the data, all covered by one signature. The namespace keeps a signature
+//! made for one purpose from being accepted for another.
+
+use crate::ec::Algorithm;
+use crate::paseto::pae;
+use crate::seed::Seed;
+use crate::sign::{self, KeyFormat};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha512};
+use std::path::Path;
+
+const TYPE: &str = "1seed-signature";
+const VERSION: u32 = 1;
+
+fn b64() -> base64::engine::GeneralPurpose {
+    base64::engine::general_purpose::STANDARD
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Envelope {
+    #[serde(rename = "type")]
+    kind: String,
+    version: u32,
+    pub alg: String,
+    /// Public key as `sign pub` prints it
+    pub key: String,
+    pub namespace: String,
+    /// Unix time
+    pub created: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    sha512: String,
+    signature: String,
+}
+
+impl Envelope {
+    /// The bytes the signature covers: every field but the signature, each
+    /// length-prefixed.
+    fn signed_bytes(&self) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        Ok(pae(&[
+            TYPE.as_bytes(),
+            &VERSION.to_le_bytes(),
+            self.alg.as_bytes(),
+            &b64().decode(&self.key)?,
+            self.namespace.as_bytes(),
+            &self.created.to_le_bytes(),
+            self.comment.as_deref().unwrap_or_default().as_bytes(),
+            &b64().decode(&self.sha512)?,
+        ]))
+    }
+}
+
+/// The content hash an envelope signs, of the file (default: stdin) read
+/// a buffer at a time.
+pub fn digest_input(path: Option<&Path>) -> Result<[u8; 64], Box<dyn std::error::Error>> {
+    Ok(sign::hash_input(path, Sha512::new())?.finalize().into())
+}
+
+fn check_namespace(namespace: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if namespace.is_empty() {
+        return Err("namespace must not be empty".into());
+    }
+    Ok(())
+}
+
+/// Sign the SHA-512 `digest` of the data for `namespace`. Returns the
+/// envelope as a line of JSON.
+pub fn sign(
+    seed: &Seed,
+    realm: &str,
+    alg: Algorithm,
+    namespace: &str,
+    comment: Option<&str>,
+    created: u64,
+    digest: &[u8; 64],
+) -> Result<String, Box<dyn std::error::Error>> {
+    check_namespace(namespace)?;
+    let mut envelope = Envelope {
+        kind: TYPE.to_string(),
+        version: VERSION,
+        alg: alg.name().to_string(),
+        key: sign::public_key(seed, realm, alg, KeyFormat::Base64),
+        namespace: namespace.to_string(),
+        created,
+        comment: comment.map(str::to_string),
+        sha512: b64().encode(digest),
+        signature: String::new(),
+    };
+    let signature = sign::sign_bytes(seed, realm, alg, &envelope.signed_bytes()?);
+    envelope.signature = b64().encode(signature);
+    Ok(serde_json::to_string(&envelope)? + "\n")
+}
+
+/// Whether `text` is an envelope rather than another signature format.
+pub fn is_signature(text: &[u8]) -> bool {
+    serde_json::from_slice::<serde_json::Value>(text).is_ok_and(|v| v["type"] == TYPE)
+}
+
+/// Verify an envelope made by `key` (base64, as `sign pub` prints it) for
+/// `namespace` over data with SHA-512 `digest`. Returns the envelope, or
+/// `None` if the signature or the digest does not match.
+pub fn verify(
+    envelope: &[u8],
+    key: &str,
+    namespace: &str,
+    digest: &[u8; 64],
+) -> Result<Option<Envelope>, Box<dyn std::error::Error>> {
+    let envelope: Envelope =
+        serde_json::from_slice(envelope).map_err(|e| format!("invalid signature envelope: {e}"))?;
+    if envelope.kind != TYPE || envelope.version != VERSION {
+        return Err(format!(
+            "unsupported signature envelope {} version {}",
+            envelope.kind, envelope.version
+        )
+        .into());
+    }
+    if b64().decode(&envelope.key)? != b64().decode(key.trim())? {
+        return Err(format!("signature is from key {}, not {}", envelope.key, key.trim()).into());
+    }
+    if envelope.namespace != namespace {
+        return Err(format!(
+            "signature is for namespace '{}', not '{namespace}'",
+            envelope.namespace
+        )
+        .into());
+    }
+
+    let signature = b64().decode(&envelope.signature)?;
+    let valid = sign::verify_bytes(
+        &b64().decode(&envelope.key)?,
+        &signature,
+        &envelope.signed_bytes()?,
+    )?;
+    let hash_matches = b64().decode(&envelope.sha512)? == digest;
+    Ok((valid && hash_matches).then_some(envelope))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digest(data: &[u8]) -> [u8; 64] {
+        Sha512::digest(data).into()
+    }
+
+    #[test]
+    fn sign_and_verify() {
+        let seed = Seed::from_passphrase("test").unwrap();
+        let key = sign::derive_public(&seed, "realm");
+        let envelope = sign(
+            &seed,
+            "realm",
+            Algorithm::Ed25519,
+            "release",
+            Some("v1.0"),
+            1_700_000_000,
+            &digest(b"data"),
+        )
+        .unwrap();
+        assert!(is_signature(envelope.as_bytes()));
+
+        let signed = verify(envelope.as_bytes(), &key, "release", &digest(b"data"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(signed.created, 1_700_000_000);
+        assert_eq!(signed.comment.as_deref(), Some("v1.0"));
+
+        let check = |envelope: &str, namespace: &str, data: &[u8]| {
+            verify(envelope.as_bytes(), &key, namespace, &digest(data))
+        };
+        assert!(check(&envelope, "release", b"other").unwrap().is_none());
+        assert!(check(&envelope, "backup", b"data").is_err());
+
+        // metadata is covered by the signature
+        let tampered = envelope.replace("v1.0", "v2.0");
+        assert!(check(&tampered, "release", b"data").unwrap().is_none());
+        let renamed = envelope.replace("\"release\"", "\"backup\"");
+        assert!(check(&renamed, "backup", b"data").unwrap().is_none());
+
+        let other = sign::derive_public(&seed, "other");
+        assert!(verify(envelope.as_bytes(), &other, "release", &digest(b"data")).is_err());
+    }
+
+    #[test]
+    fn p256_envelope() {
+        let seed = Seed::from_passphrase("test").unwrap();
+        let key = sign::public_key(&seed, "realm", Algorithm::P256, KeyFormat::Base64);
+        let envelope = sign(
+            &seed,
+            "realm",
+            Algorithm::P256,
+            "file",
+            None,
+            0,
+            &digest(b"data"),
+        )
+        .unwrap();
+
+        assert!(!envelope.contains("comment"));
+        assert!(verify(envelope.as_bytes(), &key, "file", &digest(b"data"))
+            .unwrap()
+            .is_some());
+    }
+}
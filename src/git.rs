@@ -0,0 +1,338 @@
+//! Commit signing with the realm's SSH key: git configuration, the
+//! `allowed_signers` entry, and checking signed commits.
+
+use crate::seed::Seed;
+use crate::ssh::{self, KeyType};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const NAMESPACE: &str = "git";
+
+fn git_raw(args: &[&str]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .map_err(|e| format!("failed to run git: {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+    Ok(output.stdout)
+}
+
+fn git(args: &[&str]) -> Result<String, Box<dyn std::error::Error>> {
+    Ok(String::from_utf8(git_raw(args)?)?.trim_end().to_string())
+}
+
+pub struct SetupOptions {
+    /// Configure `~/.gitconfig` instead of the current repository
+    pub global: bool,
+    /// Default: git's `user.email`
+    pub email: Option<String>,
+    /// Default: `allowed_signers` in the git directory, or
+    /// `~/.config/git/allowed_signers` with `global`
+    pub allowed_signers: Option<PathBuf>,
+    pub key_type: KeyType,
+}
+
+pub struct Setup {
+    pub public_key: String,
+    pub email: String,
+    pub allowed_signers: PathBuf,
+    /// Whether the entry was new in `allowed_signers`
+    pub added: bool,
+}
+
+/// Sign commits and tags with the realm's SSH key through the
+/// `1seed-ssh-sign` stand-in, and trust that key for the user's email.
+pub fn setup(
+    seed: &Seed,
+    realm: &str,
+    opts: &SetupOptions,
+) -> Result<Setup, Box<dyn std::error::Error>> {
+    let scope = if opts.global { "--global" } else { "--local" };
+    let git_dir = if opts.global {
+        None
+    } else {
+        Some(
+            git(&["rev-parse", "--absolute-git-dir"])
+                .map_err(|_| "not in a git repository (use --global)")?,
+        )
+    };
+
+    let email = match &opts.email {
+        Some(email) => email.clone(),
+        // unscoped, so an email from ~/.gitconfig counts for a repository
+        None => {
+            git(&["config", "user.email"]).map_err(|_| "user.email is not set; pass --email")?
+        }
+    };
+    if email.is_empty() || email.contains(char::is_whitespace) {
+        return Err(format!("invalid email '{email}'").into());
+    }
+
+    let allowed_signers = match (&opts.allowed_signers, &git_dir) {
+        (Some(path), _) => std::path::absolute(path)?,
+        (None, Some(git_dir)) => Path::new(git_dir).join("allowed_signers"),
+        (None, None) => dirs::home_dir()
+            .ok_or("cannot find the home directory")?
+            .join(".config/git/allowed_signers"),
+    };
+
+    let public_key = ssh::derive_public(seed, realm, opts.key_type);
+    let added = add_allowed_signer(&allowed_signers, &email, &public_key)?;
+    let program = ssh_sign_program()?;
+
+    let path = |path: &Path| path.to_string_lossy().into_owned();
+    let settings = [
+        ("gpg.format", "ssh".to_string()),
+        ("gpg.ssh.program", path(&program)),
+        ("gpg.ssh.allowedSignersFile", path(&allowed_signers)),
+        ("user.signingkey", format!("key::{public_key}")),
+        ("commit.gpgsign", "true".to_string()),
+        ("tag.gpgsign", "true".to_string()),
+    ];
+    for (name, value) in &settings {
+        git(&["config", scope, name, value])?;
+    }
+
+    Ok(Setup {
+        public_key,
+        email,
+        allowed_signers,
+        added,
+    })
+}
+
+/// Append `email namespaces="git" key` unless an entry already allows it.
+fn add_allowed_signer(
+    path: &Path,
+    email: &str,
+    public_key: &str,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let existing = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let key = ssh_key::PublicKey::from_openssh(public_key)?;
+    if ssh::find_signer(&existing, &key, NAMESPACE, Some(email))?.is_some() {
+        return Ok(false);
+    }
+
+    let mut updated = existing;
+    if !updated.is_empty() && !updated.ends_with('\n') {
+        updated.push('\n');
+    }
+    updated.push_str(&format!(
+        "{email} namespaces=\"{NAMESPACE}\" {public_key}\n"
+    ));
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, updated)?;
+    Ok(true)
+}
+
+/// The `1seed-ssh-sign` link git runs (see `cli::run_ssh_keygen`). `make
+/// install` and install.sh put one next to the binary; otherwise it is made
+/// in the user's data directory, since the binary's own directory is often
+/// not writable.
+fn ssh_sign_program() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let exe = std::env::current_exe()?;
+    let name = format!("1seed-ssh-sign{}", std::env::consts::EXE_SUFFIX);
+    let installed = exe.with_file_name(&name);
+    if installed.exists() {
+        return Ok(installed);
+    }
+
+    let dir = dirs::data_dir()
+        .ok_or("cannot find the data directory")?
+        .join("1seed/bin");
+    std::fs::create_dir_all(&dir)?;
+    let program = dir.join(&name);
+
+    // a link to a moved or replaced binary is made again
+    #[cfg(unix)]
+    if std::fs::read_link(&program).is_ok_and(|target| target == exe) {
+        return Ok(program);
+    }
+    #[cfg(not(unix))]
+    if program.exists() {
+        return Ok(program);
+    }
+
+    let _ = std::fs::remove_file(&program);
+    #[cfg(unix)]
+    let linked = std::os::unix::fs::symlink(&exe, &program);
+    // symlinks need extra privileges on Windows; a hard link goes stale
+    // after `1seed update` and has to be made again
+    #[cfg(not(unix))]
+    let linked = std::fs::hard_link(&exe, &program);
+    linked.map_err(|e| format!("cannot create {}: {e}", program.display()))?;
+    Ok(program)
+}
+
+/// The default signers manifest: git's `gpg.ssh.allowedSignersFile`.
+pub fn allowed_signers_file() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let path = git(&["config", "--type=path", "gpg.ssh.allowedSignersFile"])
+        .map_err(|_| "gpg.ssh.allowedSignersFile is not set; pass --signers")?;
+    Ok(PathBuf::from(path))
+}
+
+pub struct CommitCheck {
+    pub commit: String,
+    pub subject: String,
+    /// The matching principals, or why the commit failed
+    pub result: Result<String, String>,
+}
+
+/// Check every commit in `range` (anything `git rev-list` takes): it must
+/// carry an SSH signature from a key that `allowed_signers` lets the
+/// committer's email use for git.
+pub fn verify_range(
+    range: &str,
+    allowed_signers: &str,
+) -> Result<Vec<CommitCheck>, Box<dyn std::error::Error>> {
+    let commits = git(&["rev-list", range])?;
+    let mut checks = Vec::new();
+    for commit in commits.lines() {
+        let raw = git_raw(&["cat-file", "commit", commit])?;
+        let (payload, signature) = split_signature(&raw);
+        let text = String::from_utf8_lossy(&raw);
+        let subject = text
+            .split_once("\n\n")
+            .and_then(|(_, body)| body.lines().next())
+            .unwrap_or_default()
+            .to_string();
+
+        let result = match signature {
+            None => Err("not signed".to_string()),
+            Some(signature) => check_commit(&text, &payload, &signature, allowed_signers),
+        };
+        checks.push(CommitCheck {
+            commit: commit.to_string(),
+            subject,
+            result,
+        });
+    }
+    Ok(checks)
+}
+
+fn check_commit(
+    text: &str,
+    payload: &[u8],
+    signature: &str,
+    allowed_signers: &str,
+) -> Result<String, String> {
+    if !signature.starts_with("-----BEGIN SSH SIGNATURE-----") {
+        return Err("not an SSH signature".to_string());
+    }
+    let email = text
+        .lines()
+        .take_while(|line| !line.is_empty())
+        .find_map(|line| line.strip_prefix("committer "))
+        .and_then(|committer| committer.split_once('<'))
+        .and_then(|(_, rest)| rest.split_once('>'))
+        .map(|(email, _)| email)
+        .ok_or("no committer email")?;
+
+    let key = ssh::check_signature(signature, NAMESPACE, payload).map_err(|e| e.to_string())?;
+    ssh::find_signer(allowed_signers, &key, NAMESPACE, Some(email))
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| {
+            format!(
+                "key {} is not allowed for {email}",
+                key.fingerprint(ssh_key::HashAlg::Sha256)
+            )
+        })
+}
+
+/// Separate a raw commit into the signed payload (the commit without its
+/// `gpgsig` header) and the signature.
+fn split_signature(raw: &[u8]) -> (Vec<u8>, Option<String>) {
+    let header_end = raw
+        .windows(2)
+        .position(|w| w == b"\n\n")
+        .map_or(raw.len(), |i| i + 1);
+
+    let mut payload = Vec::with_capacity(raw.len());
+    let mut signature: Option<String> = None;
+    let mut in_signature = false;
+    for line in raw[..header_end].split_inclusive(|&b| b == b'\n') {
+        if let Some(value) = line.strip_prefix(b"gpgsig ") {
+            signature = Some(String::from_utf8_lossy(value).into_owned());
+            in_signature = true;
+        } else if in_signature && line.starts_with(b" ") {
+            let signature = signature.as_mut().expect("in a signature");
+            signature.push_str(&String::from_utf8_lossy(&line[1..]));
+        } else {
+            in_signature = false;
+            payload.extend_from_slice(line);
+        }
+    }
+    payload.extend_from_slice(&raw[header_end..]);
+    (payload, signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signature_split_from_commit() {
+        let raw = b"tree 4b825dc642cb6eb9a060e54bf8d69288fbee4904\n\
+author A <a@example.com> 0 +0000\n\
+committer A <a@example.com> 0 +0000\n\
+gpgsig -----BEGIN SSH SIGNATURE-----\n \
+U1NIU0lH\n \
+-----END SSH SIGNATURE-----\n\
+\n\
+subject\n\
+\n\
+gpgsig in the body stays\n";
+
+        let (payload, signature) = split_signature(raw);
+        assert_eq!(
+            signature.as_deref(),
+            Some("-----BEGIN SSH SIGNATURE-----\nU1NIU0lH\n-----END SSH SIGNATURE-----\n")
+        );
+        assert_eq!(
+            String::from_utf8(payload).unwrap(),
+            "tree 4b825dc642cb6eb9a060e54bf8d69288fbee4904\n\
+author A <a@example.com> 0 +0000\n\
+committer A <a@example.com> 0 +0000\n\
+\n\
+subject\n\
+\n\
+gpgsig in the body stays\n"
+        );
+    }
+
+    #[test]
+    fn signed_commit_checked_against_signers() {
+        let seed = Seed::from_passphrase("test").unwrap();
+        let payload = "tree 4b825dc642cb6eb9a060e54bf8d69288fbee4904\n\
+committer A <a@example.com> 0 +0000\n\nsubject\n";
+        let signature = ssh::sign_data(
+            &seed,
+            "realm",
+            KeyType::Ed25519,
+            NAMESPACE,
+            payload.as_bytes(),
+        )
+        .unwrap();
+        let public = ssh::derive_public(&seed, "realm", KeyType::Ed25519);
+
+        let allowed = format!("a@example.com {public}\n");
+        let check = |allowed: &str| check_commit(payload, payload.as_bytes(), &signature, allowed);
+        assert_eq!(check(&allowed).as_deref(), Ok("a@example.com"));
+        assert!(check(&format!("b@example.com {public}\n")).is_err());
+        assert!(check("").is_err());
+    }
+}
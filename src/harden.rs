@@ -0,0 +1,137 @@
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use zeroize::Zeroizing;
+
+static HARDENED: AtomicBool = AtomicBool::new(false);
+static LOCK_WARNED: AtomicBool = AtomicBool::new(false);
+
+/// Disable core dumps and turn on memory locking for secret buffers.
+pub fn enable() -> Result<(), Box<dyn std::error::Error>> {
+    #[cfg(unix)]
+    {
+        let limit = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        if unsafe { libc::setrlimit(libc::RLIMIT_CORE, &limit) } != 0 {
+            return Err(format!(
+                "failed to disable core dumps: {}",
+                std::io::Error::last_os_error()
+            )
+            .into());
+        }
+    }
+
+    // also blocks ptrace attach by other processes of the same user
+    #[cfg(target_os = "linux")]
+    if unsafe { libc::prctl(libc::PR_SET_DUMPABLE, 0, 0, 0, 0) } != 0 {
+        return Err(format!(
+            "failed to mark process undumpable: {}",
+            std::io::Error::last_os_error()
+        )
+        .into());
+    }
+
+    HARDENED.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Keep `buf` out of swap. No-op unless hardening is enabled; failures
+/// (usually RLIMIT_MEMLOCK) warn once instead of aborting.
+///
+/// There is deliberately no unlock: mlock works on whole pages and is not
+/// counted, so unlocking one buffer could unlock a neighbouring secret on the
+/// same page. Locked pages stay locked until the process exits.
+pub fn lock(buf: &[u8]) {
+    if !HARDENED.load(Ordering::Relaxed) || buf.is_empty() {
+        return;
+    }
+
+    #[cfg(unix)]
+    if unsafe { libc::mlock(buf.as_ptr().cast(), buf.len()) } != 0
+        && !LOCK_WARNED.swap(true, Ordering::Relaxed)
+    {
+        eprintln!(
+            "warning: could not lock secret memory: {}",
+            std::io::Error::last_os_error()
+        );
+    }
+}
+
+/// Like `Read::read_to_end`, but every buffer is locked while in use and
+/// zeroized when the data outgrows it, so no partial copies are left behind.
+/// Only for secret input; everything it reads counts against RLIMIT_MEMLOCK.
+pub fn read_all(mut reader: impl Read) -> std::io::Result<Zeroizing<Vec<u8>>> {
+    let mut buf = locked_buffer(8192);
+    let mut len = 0;
+
+    loop {
+        if len == buf.len() {
+            let mut bigger = locked_buffer(buf.len() * 2);
+            bigger[..len].copy_from_slice(&buf[..len]);
+            buf = bigger;
+        }
+
+        match reader.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    let mut data = Zeroizing::new(vec![0u8; len]);
+    lock(&data);
+    data.copy_from_slice(&buf[..len]);
+    Ok(data)
+}
+
+/// Like `std::io::copy`, but through a locked buffer that is zeroized
+/// afterwards, for streams of plaintext.
+pub fn copy(reader: &mut impl Read, writer: &mut impl Write) -> std::io::Result<u64> {
+    let mut buf = locked_buffer(64 * 1024);
+    let mut total = 0;
+
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => return Ok(total),
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        writer.write_all(&buf[..n])?;
+        total += n as u64;
+    }
+}
+
+fn locked_buffer(size: usize) -> Zeroizing<Vec<u8>> {
+    let buf = Zeroizing::new(vec![0u8; size]);
+    lock(&buf);
+    buf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_all_across_growth() {
+        let input: Vec<u8> = (0..50_000u32).map(|i| i as u8).collect();
+        let data = read_all(&input[..]).unwrap();
+        assert_eq!(data.as_slice(), input.as_slice());
+    }
+
+    #[test]
+    fn read_all_empty() {
+        let data = read_all(&[][..]).unwrap();
+        assert!(data.is_empty());
+    }
+
+    #[test]
+    fn copy_larger_than_buffer() {
+        let input: Vec<u8> = (0..200_000u32).map(|i| i as u8).collect();
+        let mut output = Vec::new();
+        assert_eq!(copy(&mut &input[..], &mut output).unwrap(), 200_000);
+        assert_eq!(output, input);
+    }
+}
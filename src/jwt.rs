@@ -0,0 +1,218 @@
+use crate::ec;
+use crate::seed::Seed;
+use crate::sign;
+use base64::Engine;
+use ed25519_dalek::{Signature, Signer, Verifier, VerifyingKey};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+fn b64url() -> base64::engine::GeneralPurpose {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD
+}
+
+/// RFC 7638 JWK thumbprint, so the `kid` changes only with the key.
+pub fn kid(key: &VerifyingKey) -> String {
+    let canonical = format!(
+        r#"{{"crv":"Ed25519","kty":"OKP","x":"{}"}}"#,
+        b64url().encode(key.as_bytes())
+    );
+    b64url().encode(Sha256::digest(canonical.as_bytes()))
+}
+
+/// A verification key, as found in a JWKS document.
+pub struct Jwk {
+    pub kid: Option<String>,
+    pub key: VerifyingKey,
+}
+
+impl Jwk {
+    pub fn new(key: VerifyingKey) -> Self {
+        Jwk {
+            kid: Some(kid(&key)),
+            key,
+        }
+    }
+}
+
+/// JWKS document with the public signing key of each realm.
+pub fn jwks(seed: &Seed, realms: &[&str]) -> String {
+    let keys: Vec<Value> = realms
+        .iter()
+        .map(|realm| {
+            let key = sign::signing_key(seed, realm);
+            let mut jwk: Value =
+                serde_json::from_str(&ec::ed25519_jwk(&key, false)).expect("valid JSON");
+            jwk["kid"] = kid(&key.verifying_key()).into();
+            jwk["alg"] = "EdDSA".into();
+            jwk["use"] = "sig".into();
+            jwk
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&json!({ "keys": keys })).expect("valid JSON")
+}
+
+/// EdDSA JWT over `claims` (a JSON object). `iat` defaults to now; with
+/// `expires_in`, `exp` is set that many seconds later.
+pub fn sign(
+    seed: &Seed,
+    realm: &str,
+    claims: &[u8],
+    expires_in: Option<u64>,
+    now: u64,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut claims: Value =
+        serde_json::from_slice(claims).map_err(|e| format!("invalid claims: {e}"))?;
+    let object = claims
+        .as_object_mut()
+        .ok_or("invalid claims: expected a JSON object")?;
+    object.entry("iat").or_insert(now.into());
+    if let Some(seconds) = expires_in {
+        object.insert("exp".into(), now.saturating_add(seconds).into());
+    }
+
+    let key = sign::signing_key(seed, realm);
+    let header = json!({
+        "alg": "EdDSA",
+        "typ": "JWT",
+        "kid": kid(&key.verifying_key()),
+    });
+
+    let signing_input = format!(
+        "{}.{}",
+        b64url().encode(header.to_string()),
+        b64url().encode(claims.to_string())
+    );
+    let signature = key.sign(signing_input.as_bytes());
+
+    Ok(format!(
+        "{signing_input}.{}",
+        b64url().encode(signature.to_bytes())
+    ))
+}
+
+/// Public keys from a JWKS document; non-Ed25519 entries are skipped.
+pub fn parse_jwks(document: &[u8]) -> Result<Vec<Jwk>, Box<dyn std::error::Error>> {
+    let document: Value =
+        serde_json::from_slice(document).map_err(|e| format!("invalid JWKS: {e}"))?;
+    let keys = document["keys"]
+        .as_array()
+        .ok_or("invalid JWKS: missing 'keys'")?;
+
+    let mut out = Vec::new();
+    for jwk in keys {
+        if jwk["kty"] != "OKP" || jwk["crv"] != "Ed25519" {
+            continue;
+        }
+        let x = jwk["x"].as_str().ok_or("invalid JWKS: key without 'x'")?;
+        let bytes: [u8; 32] = b64url()
+            .decode(x)?
+            .try_into()
+            .map_err(|_| "invalid JWKS: bad Ed25519 key length")?;
+        out.push(Jwk {
+            kid: jwk["kid"].as_str().map(str::to_string),
+            key: VerifyingKey::from_bytes(&bytes)?,
+        });
+    }
+    if out.is_empty() {
+        return Err("JWKS has no Ed25519 keys".into());
+    }
+    Ok(out)
+}
+
+/// Check signature, `exp` and `nbf`; returns the claims.
+pub fn verify(token: &str, keys: &[Jwk], now: u64) -> Result<Value, Box<dyn std::error::Error>> {
+    let mut parts = token.trim().split('.');
+    let (Some(header), Some(claims), Some(signature), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err("invalid token: expected three dot-separated parts".into());
+    };
+
+    let header: Value = serde_json::from_slice(&b64url().decode(header)?)
+        .map_err(|e| format!("invalid token header: {e}"))?;
+    if header["alg"] != "EdDSA" {
+        return Err(format!("unsupported algorithm {}", header["alg"]).into());
+    }
+
+    let signature: [u8; 64] = b64url()
+        .decode(signature)?
+        .try_into()
+        .map_err(|_| "invalid signature length")?;
+    let signature = Signature::from_bytes(&signature);
+    let signing_input = &token.trim()[..token.trim().rfind('.').expect("three parts")];
+
+    let kid = header["kid"].as_str();
+    let valid = keys
+        .iter()
+        .filter(|jwk| kid.is_none() || jwk.kid.is_none() || jwk.kid.as_deref() == kid)
+        .any(|jwk| jwk.key.verify(signing_input.as_bytes(), &signature).is_ok());
+    if !valid {
+        return Err("invalid signature".into());
+    }
+
+    let claims: Value = serde_json::from_slice(&b64url().decode(claims)?)
+        .map_err(|e| format!("invalid claims: {e}"))?;
+    if let Some(exp) = claims.get("exp") {
+        let exp = exp
+            .as_u64()
+            .ok_or("invalid claims: 'exp' must be a unix time")?;
+        if now >= exp {
+            return Err("token expired".into());
+        }
+    }
+    if let Some(nbf) = claims.get("nbf") {
+        let nbf = nbf
+            .as_u64()
+            .ok_or("invalid claims: 'nbf' must be a unix time")?;
+        if now < nbf {
+            return Err("token not yet valid".into());
+        }
+    }
+    Ok(claims)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn realm_keys(seed: &Seed, realm: &str) -> Vec<Jwk> {
+        vec![Jwk::new(sign::signing_key(seed, realm).verifying_key())]
+    }
+
+    #[test]
+    fn rfc8037_thumbprint() {
+        let x = b64url()
+            .decode("11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo")
+            .unwrap();
+        let key = VerifyingKey::from_bytes(&x.try_into().unwrap()).unwrap();
+
+        assert_eq!(kid(&key), "kPrK_qmxVWaYVA9wwBF6Iuo3vVzz7TxHCTwXBygrS4k");
+    }
+
+    #[test]
+    fn sign_and_verify() {
+        let seed = Seed::from_passphrase("test").unwrap();
+        let now = 1_700_000_000;
+
+        let token = sign(&seed, "realm", br#"{"sub":"svc"}"#, Some(3600), now).unwrap();
+        let claims = verify(&token, &realm_keys(&seed, "realm"), now + 60).unwrap();
+
+        assert_eq!(claims["sub"], "svc");
+        assert_eq!(claims["iat"], now);
+        assert_eq!(claims["exp"], now + 3600);
+        assert!(verify(&token, &realm_keys(&seed, "realm"), now + 3600).is_err());
+        assert!(verify(&token, &realm_keys(&seed, "other"), now).is_err());
+    }
+
+    #[test]
+    fn jwks_round_trip() {
+        let seed = Seed::from_passphrase("test").unwrap();
+        let document = jwks(&seed, &["a", "b"]);
+        let keys = parse_jwks(document.as_bytes()).unwrap();
+
+        let token = sign(&seed, "b", b"{}", None, 0).unwrap();
+        assert_eq!(keys.len(), 2);
+        assert!(verify(&token, &keys, 0).is_ok());
+    }
+}
@@ -1,16 +1,47 @@
 mod age;
+mod bip32;
+mod bip85;
 mod cli;
 mod derive;
+mod doctor;
+mod ec;
+mod envelope;
+mod git;
+mod harden;
+mod jwt;
+mod minisign;
+mod nostr;
+mod otp;
+mod paseto;
 mod password;
+mod pgp;
+mod rsa;
 mod seed;
 mod sign;
+mod signify;
 mod ssh;
 mod update;
+mod wg;
+mod x509;
 
 use std::process::ExitCode;
 
 fn main() -> ExitCode {
-    match cli::run() {
+    // git's gpg.ssh.program cannot take arguments, so the ssh-keygen
+    // stand-in is picked by the name we run under (a link to 1seed)
+    let ssh_sign = std::env::args_os()
+        .next()
+        .as_deref()
+        .map(std::path::Path::new)
+        .and_then(std::path::Path::file_stem)
+        .is_some_and(|name| name == "1seed-ssh-sign");
+
+    let result = if ssh_sign {
+        cli::run_ssh_keygen()
+    } else {
+        cli::run()
+    };
+    match result {
         Ok(()) => ExitCode::SUCCESS,
         Err(e) => {
             eprintln!("error: {e}");
@@ -0,0 +1,205 @@
+//! Minisign public keys and signatures over the realm's Ed25519 signing
+//! key, verifiable with `minisign -V` and compatible tools.
+
+use crate::seed::Seed;
+use crate::sign;
+use base64::Engine;
+use blake2::{Blake2b512, Digest};
+use ed25519_dalek::{Signature, Signer, Verifier, VerifyingKey};
+
+const UNTRUSTED: &str = "untrusted comment: ";
+const TRUSTED: &str = "trusted comment: ";
+
+fn b64() -> base64::engine::GeneralPurpose {
+    base64::engine::general_purpose::STANDARD
+}
+
+/// The key ID as minisign prints it: a little-endian integer in hex.
+fn key_id_hex(id: &[u8; 8]) -> String {
+    format!("{:016X}", u64::from_le_bytes(*id))
+}
+
+/// A minisign public key: the Ed25519 key and the ID signatures refer to.
+/// Signify public keys have the same layout.
+pub struct PublicKey {
+    pub id: [u8; 8],
+    pub key: VerifyingKey,
+}
+
+impl PublicKey {
+    pub fn new(key: VerifyingKey) -> Self {
+        PublicKey {
+            id: sign::key_id(&key),
+            key,
+        }
+    }
+
+    /// Parse the base64 line or a whole `.pub` file.
+    pub fn parse(text: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let line = text
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with(UNTRUSTED))
+            .ok_or("invalid public key")?;
+        let bytes = b64().decode(line)?;
+        if bytes.len() != 42 || &bytes[..2] != b"Ed" {
+            return Err("invalid public key: expected minisign or signify format".into());
+        }
+        Ok(PublicKey {
+            id: bytes[2..10].try_into().expect("eight bytes"),
+            key: VerifyingKey::from_bytes(&bytes[10..].try_into().expect("32 bytes"))?,
+        })
+    }
+
+    /// Contents of a `minisign.pub` file.
+    pub fn encode(&self) -> String {
+        let mut bytes = b"Ed".to_vec();
+        bytes.extend_from_slice(&self.id);
+        bytes.extend_from_slice(self.key.as_bytes());
+        format!(
+            "{UNTRUSTED}minisign public key {}\n{}\n",
+            key_id_hex(&self.id),
+            b64().encode(bytes)
+        )
+    }
+}
+
+pub fn public_key(seed: &Seed, realm: &str) -> String {
+    PublicKey::new(sign::signing_key(seed, realm).verifying_key()).encode()
+}
+
+fn check_comment(comment: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if comment.contains(['\n', '\r']) {
+        return Err("comments must be a single line".into());
+    }
+    Ok(())
+}
+
+/// A `.minisig` signature in prehashed mode (BLAKE2b-512 of the data is
+/// signed), the only mode current minisign accepts by default. The trusted
+/// comment is signed together with the signature.
+pub fn sign(
+    seed: &Seed,
+    realm: &str,
+    data: &[u8],
+    trusted_comment: &str,
+    untrusted_comment: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    check_comment(trusted_comment)?;
+    check_comment(untrusted_comment)?;
+
+    let key = sign::signing_key(seed, realm);
+    let signature = key.sign(&Blake2b512::digest(data)).to_bytes();
+
+    let mut global = signature.to_vec();
+    global.extend_from_slice(trusted_comment.as_bytes());
+    let global = key.sign(&global).to_bytes();
+
+    let mut bytes = b"ED".to_vec();
+    bytes.extend_from_slice(&sign::key_id(&key.verifying_key()));
+    bytes.extend_from_slice(&signature);
+    Ok(format!(
+        "{UNTRUSTED}{untrusted_comment}\n{}\n{TRUSTED}{trusted_comment}\n{}\n",
+        b64().encode(bytes),
+        b64().encode(global)
+    ))
+}
+
+/// Whether `text` looks like a `.minisig` file: unlike signify's, it has a
+/// trusted comment.
+pub fn is_signature(text: &[u8]) -> bool {
+    let text = String::from_utf8_lossy(text);
+    text.starts_with(UNTRUSTED) && text.lines().nth(2).is_some_and(|l| l.starts_with(TRUSTED))
+}
+
+/// Verify a `.minisig` signature, prehashed or legacy, and its trusted
+/// comment. Returns the trusted comment, or `None` if either signature is
+/// invalid.
+pub fn verify(
+    key: &PublicKey,
+    signature: &str,
+    data: &[u8],
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let mut lines = signature.lines();
+    let (Some(_), Some(sig_line), Some(comment_line), Some(global_line)) =
+        (lines.next(), lines.next(), lines.next(), lines.next())
+    else {
+        return Err("invalid minisign signature: expected four lines".into());
+    };
+
+    let bytes = b64().decode(sig_line.trim())?;
+    if bytes.len() != 74 {
+        return Err("invalid minisign signature".into());
+    }
+    let id: [u8; 8] = bytes[2..10].try_into().expect("eight bytes");
+    if id != key.id {
+        return Err(format!(
+            "signature is from key {}, not {}",
+            key_id_hex(&id),
+            key_id_hex(&key.id)
+        )
+        .into());
+    }
+    let signature = Signature::from_slice(&bytes[10..])?;
+
+    let valid = match &bytes[..2] {
+        b"ED" => key
+            .key
+            .verify(&Blake2b512::digest(data), &signature)
+            .is_ok(),
+        b"Ed" => key.key.verify(data, &signature).is_ok(),
+        _ => return Err("unsupported minisign signature algorithm".into()),
+    };
+
+    let trusted_comment = comment_line
+        .strip_prefix(TRUSTED)
+        .ok_or("invalid minisign signature: missing trusted comment")?;
+    let mut global = signature.to_bytes().to_vec();
+    global.extend_from_slice(trusted_comment.as_bytes());
+    let global_signature = Signature::from_slice(&b64().decode(global_line.trim())?)?;
+    let valid = valid && key.key.verify(&global, &global_signature).is_ok();
+
+    Ok(valid.then(|| trusted_comment.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn realm_key(seed: &Seed, realm: &str) -> PublicKey {
+        PublicKey::new(sign::signing_key(seed, realm).verifying_key())
+    }
+
+    #[test]
+    fn minisign_can_verify() {
+        let seed = Seed::from_passphrase("test").unwrap();
+        let public = public_key(&seed, "realm");
+        let signature = sign(&seed, "realm", b"data", "timestamp:0", "comment").unwrap();
+
+        let theirs = minisign_verify::PublicKey::decode(&public).unwrap();
+        let decoded = minisign_verify::Signature::decode(&signature).unwrap();
+        assert_eq!(decoded.trusted_comment(), "timestamp:0");
+        assert_eq!(decoded.untrusted_comment(), "untrusted comment: comment");
+        theirs.verify(b"data", &decoded, false).unwrap();
+        assert!(theirs.verify(b"other", &decoded, false).is_err());
+    }
+
+    #[test]
+    fn sign_and_verify() {
+        let seed = Seed::from_passphrase("test").unwrap();
+        let key = PublicKey::parse(&public_key(&seed, "realm")).unwrap();
+        assert_eq!(key.key, realm_key(&seed, "realm").key);
+
+        let signature = sign(&seed, "realm", b"data", "hello", "").unwrap();
+        assert_eq!(
+            verify(&key, &signature, b"data").unwrap().as_deref(),
+            Some("hello")
+        );
+        assert_eq!(verify(&key, &signature, b"other").unwrap(), None);
+
+        let tampered = signature.replace("hello", "hellO");
+        assert_eq!(verify(&key, &tampered, b"data").unwrap(), None);
+        assert!(verify(&realm_key(&seed, "other"), &signature, b"data").is_err());
+        assert!(sign(&seed, "realm", b"data", "a\nb", "").is_err());
+    }
+}
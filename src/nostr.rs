@@ -0,0 +1,196 @@
+use crate::seed::Seed;
+use k256::schnorr::SigningKey;
+use rand_core::{OsRng, RngCore};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::time::{SystemTime, UNIX_EPOCH};
+use zeroize::Zeroizing;
+
+/// secp256k1 key by rejection sampling, like `ec::derive_p256`.
+fn derive_key(seed: &Seed, realm: &str) -> SigningKey {
+    let mut attempt = 0u32;
+    loop {
+        let candidate = if attempt == 0 {
+            seed.derive_32(realm, "nostr")
+        } else {
+            seed.derive_32(realm, &format!("nostr/{attempt}"))
+        };
+        if let Ok(key) = SigningKey::from_bytes(&*candidate) {
+            return key;
+        }
+        attempt += 1;
+    }
+}
+
+/// x-only public key as lowercase hex, as used in event JSON.
+pub fn public_hex(seed: &Seed, realm: &str) -> String {
+    hex::encode(derive_key(seed, realm).verifying_key().to_bytes())
+}
+
+/// NIP-19 `npub`.
+pub fn derive_public(seed: &Seed, realm: &str) -> String {
+    npub(&derive_key(seed, realm).verifying_key().to_bytes().into())
+}
+
+/// NIP-19 `nsec`.
+pub fn derive_private(seed: &Seed, realm: &str) -> Zeroizing<String> {
+    let secret = Zeroizing::new(<[u8; 32]>::from(derive_key(seed, realm).to_bytes()));
+    nsec(&secret)
+}
+
+fn npub(public: &[u8; 32]) -> String {
+    use bech32::{ToBase32, Variant};
+    bech32::encode("npub", public.to_base32(), Variant::Bech32).expect("valid bech32")
+}
+
+fn nsec(secret: &[u8; 32]) -> Zeroizing<String> {
+    // same approach as the age identity: no reallocation, no stray copies
+    use bech32::{ToBase32, Variant};
+    let mut encoded = Zeroizing::new(String::with_capacity(64));
+    let mut writer =
+        bech32::Bech32Writer::new("nsec", Variant::Bech32, &mut *encoded).expect("valid bech32");
+    secret.write_base32(&mut writer).expect("valid bech32");
+    writer.finalize().expect("valid bech32");
+    encoded
+}
+
+/// Fill in `pubkey`, `id` and `sig` of a NIP-01 event. `created_at`
+/// defaults to now and `tags` to empty; an existing `pubkey` must be ours,
+/// and any existing `id` and `sig` are replaced.
+pub fn sign_event(
+    seed: &Seed,
+    realm: &str,
+    event: &[u8],
+) -> Result<String, Box<dyn std::error::Error>> {
+    let event: Value = serde_json::from_slice(event).map_err(|e| format!("invalid event: {e}"))?;
+    let fields = event
+        .as_object()
+        .ok_or("invalid event: expected a JSON object")?;
+
+    for name in fields.keys() {
+        if !matches!(
+            name.as_str(),
+            "id" | "pubkey" | "created_at" | "kind" | "tags" | "content" | "sig"
+        ) {
+            return Err(format!("invalid event: unknown field '{name}'").into());
+        }
+    }
+
+    let key = derive_key(seed, realm);
+    let pubkey = hex::encode(key.verifying_key().to_bytes());
+    if let Some(given) = fields.get("pubkey") {
+        if given.as_str() != Some(pubkey.as_str()) {
+            return Err("event pubkey does not match this realm's key".into());
+        }
+    }
+
+    let created_at = match fields.get("created_at") {
+        Some(t) => t
+            .as_u64()
+            .ok_or("invalid event: created_at must be a unix time")?,
+        None => SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+    };
+    let kind = fields
+        .get("kind")
+        .and_then(Value::as_u64)
+        .filter(|&k| k <= u16::MAX as u64)
+        .ok_or("invalid event: kind must be an integer between 0 and 65535")?;
+    let tags = match fields.get("tags") {
+        Some(tags) => {
+            let valid = tags.as_array().is_some_and(|tags| {
+                tags.iter().all(|tag| {
+                    tag.as_array()
+                        .is_some_and(|items| items.iter().all(Value::is_string))
+                })
+            });
+            if !valid {
+                return Err("invalid event: tags must be an array of string arrays".into());
+            }
+            tags.clone()
+        }
+        None => json!([]),
+    };
+    let content = fields
+        .get("content")
+        .and_then(Value::as_str)
+        .ok_or("invalid event: content must be a string")?;
+
+    let id = event_id(&pubkey, created_at, kind, &tags, content);
+    let mut aux_rand = [0u8; 32];
+    OsRng.fill_bytes(&mut aux_rand);
+    let sig = key.sign_prehash_with_aux_rand(&id, &aux_rand)?;
+
+    Ok(json!({
+        "id": hex::encode(id),
+        "pubkey": pubkey,
+        "created_at": created_at,
+        "kind": kind,
+        "tags": tags,
+        "content": content,
+        "sig": hex::encode(sig.to_bytes()),
+    })
+    .to_string())
+}
+
+/// NIP-01: SHA-256 of the compact JSON array
+/// `[0, pubkey, created_at, kind, tags, content]`.
+fn event_id(pubkey: &str, created_at: u64, kind: u64, tags: &Value, content: &str) -> [u8; 32] {
+    let commitment = json!([0, pubkey, created_at, kind, tags, content]).to_string();
+    Sha256::digest(commitment.as_bytes()).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k256::schnorr::signature::hazmat::PrehashVerifier;
+    use k256::schnorr::{Signature, VerifyingKey};
+
+    #[test]
+    fn nip19_vectors() {
+        let public =
+            hex::decode("7e7e9c42a91bfef19fa929e5fda1b72e0ebc1a4c1141673e2794234d86addf4e")
+                .unwrap();
+        let secret =
+            hex::decode("67dea2ed018072d675f5415ecfaed7d2597555e202d85b3d65ea4e58d2d92ffa")
+                .unwrap();
+
+        assert_eq!(
+            npub(&public.try_into().unwrap()),
+            "npub10elfcs4fr0l0r8af98jlmgdh9c8tcxjvz9qkw038js35mp4dma8qzvjptg"
+        );
+        assert_eq!(
+            nsec(&secret.try_into().unwrap()).as_str(),
+            "nsec1vl029mgpspedva04g90vltkh6fvh240zqtv9k0t9af8935ke9laqsnlfe5"
+        );
+    }
+
+    #[test]
+    fn signed_event_verifies() {
+        let seed = Seed::from_passphrase("test").unwrap();
+        let input = r#"{"kind":1,"created_at":1700000000,"tags":[["t","a\"b"]],"content":"hi\né"}"#;
+
+        let event: Value =
+            serde_json::from_str(&sign_event(&seed, "realm", input.as_bytes()).unwrap()).unwrap();
+        let pubkey = event["pubkey"].as_str().unwrap();
+        assert_eq!(pubkey, public_hex(&seed, "realm"));
+
+        let commitment = format!(r#"[0,"{pubkey}",1700000000,1,[["t","a\"b"]],"hi\né"]"#);
+        let id = Sha256::digest(commitment.as_bytes());
+        assert_eq!(event["id"], hex::encode(id));
+
+        let key = VerifyingKey::from_bytes(&hex::decode(pubkey).unwrap()).unwrap();
+        let sig =
+            Signature::try_from(&hex::decode(event["sig"].as_str().unwrap()).unwrap()[..]).unwrap();
+        key.verify_prehash(&id, &sig).unwrap();
+    }
+
+    #[test]
+    fn foreign_pubkey_rejected() {
+        let seed = Seed::from_passphrase("test").unwrap();
+        let other = public_hex(&seed, "other");
+        let input = format!(r#"{{"pubkey":"{other}","kind":1,"content":""}}"#);
+
+        assert!(sign_event(&seed, "realm", input.as_bytes()).is_err());
+        assert!(sign_event(&seed, "realm", br#"{"kind":1}"#).is_err());
+    }
+}
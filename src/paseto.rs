@@ -0,0 +1,278 @@
+//! PASETO version 4 tokens: `v4.public` signed with the realm's Ed25519
+//! signing key, `v4.local` encrypted with a key derived for the realm.
+
+use crate::seed::Seed;
+use crate::sign;
+use base64::Engine;
+use blake2::digest::consts::{U32, U56};
+use blake2::digest::Mac;
+use blake2::Blake2bMac;
+use chacha20::cipher::{KeyIvInit, StreamCipher};
+use chacha20::XChaCha20;
+use ed25519_dalek::{Signature, Signer, Verifier, VerifyingKey};
+use rand_core::{OsRng, RngCore};
+use zeroize::Zeroizing;
+
+const PUBLIC: &str = "v4.public.";
+const LOCAL: &str = "v4.local.";
+
+fn b64url() -> base64::engine::GeneralPurpose {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD
+}
+
+/// Pre-authentication encoding: every piece is length-prefixed so no two
+/// inputs produce the same bytes. Also used for `envelope` signatures.
+pub fn pae(pieces: &[&[u8]]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&(pieces.len() as u64).to_le_bytes());
+    for piece in pieces {
+        out.extend_from_slice(&(piece.len() as u64).to_le_bytes());
+        out.extend_from_slice(piece);
+    }
+    out
+}
+
+fn local_key(seed: &Seed, realm: &str) -> Zeroizing<[u8; 32]> {
+    seed.derive_32(realm, "paseto/local")
+}
+
+/// The realm's public key as a PASERK (`k4.public.`), for verifiers.
+pub fn public_key(seed: &Seed, realm: &str) -> String {
+    let key = sign::signing_key(seed, realm).verifying_key();
+    format!("k4.public.{}", b64url().encode(key.as_bytes()))
+}
+
+pub fn parse_public_key(paserk: &str) -> Result<VerifyingKey, Box<dyn std::error::Error>> {
+    let bytes: [u8; 32] = paserk
+        .trim()
+        .strip_prefix("k4.public.")
+        .and_then(|key| b64url().decode(key).ok())
+        .and_then(|key| key.try_into().ok())
+        .ok_or("invalid public key: expected k4.public.<base64url>")?;
+    Ok(VerifyingKey::from_bytes(&bytes)?)
+}
+
+/// Split off the header and optional footer, checking the footer if one is
+/// expected.
+fn split(
+    token: &str,
+    header: &str,
+    footer: Option<&[u8]>,
+) -> Result<(Vec<u8>, Vec<u8>), Box<dyn std::error::Error>> {
+    let body = token
+        .trim()
+        .strip_prefix(header)
+        .ok_or_else(|| format!("not a {} token", header.trim_end_matches('.')))?;
+    let (body, found) = match body.split_once('.') {
+        Some((body, found)) => (body, b64url().decode(found)?),
+        None => (body, Vec::new()),
+    };
+    if let Some(expected) = footer {
+        if found != expected {
+            return Err("token footer does not match".into());
+        }
+    }
+    Ok((b64url().decode(body)?, found))
+}
+
+fn assemble(header: &str, body: &[u8], footer: &[u8]) -> String {
+    let mut token = format!("{header}{}", b64url().encode(body));
+    if !footer.is_empty() {
+        token.push('.');
+        token.push_str(&b64url().encode(footer));
+    }
+    token
+}
+
+pub fn sign(seed: &Seed, realm: &str, message: &[u8], footer: &[u8], implicit: &[u8]) -> String {
+    let key = sign::signing_key(seed, realm);
+    let signature = key.sign(&pae(&[PUBLIC.as_bytes(), message, footer, implicit]));
+
+    let mut body = message.to_vec();
+    body.extend_from_slice(&signature.to_bytes());
+    assemble(PUBLIC, &body, footer)
+}
+
+/// Verify a `v4.public` token and return its message. With `footer`,
+/// the token's footer must match it.
+pub fn verify(
+    token: &str,
+    key: &VerifyingKey,
+    footer: Option<&[u8]>,
+    implicit: &[u8],
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let (body, footer) = split(token, PUBLIC, footer)?;
+    if body.len() < 64 {
+        return Err("invalid token: too short".into());
+    }
+    let (message, signature) = body.split_at(body.len() - 64);
+    let signature = Signature::from_slice(signature)?;
+
+    key.verify(
+        &pae(&[PUBLIC.as_bytes(), message, &footer, implicit]),
+        &signature,
+    )
+    .map_err(|_| "invalid signature")?;
+    Ok(message.to_vec())
+}
+
+/// Encryption key, counter nonce and authentication key for one token.
+fn local_keys(key: &[u8; 32], nonce: &[u8]) -> (Zeroizing<[u8; 56]>, Zeroizing<[u8; 32]>) {
+    let mut mac = Blake2bMac::<U56>::new_from_slice(key).expect("valid key length");
+    mac.update(b"paseto-encryption-key");
+    mac.update(nonce);
+    let encryption = Zeroizing::new(mac.finalize().into_bytes().into());
+
+    let mut mac = Blake2bMac::<U32>::new_from_slice(key).expect("valid key length");
+    mac.update(b"paseto-auth-key-for-aead");
+    mac.update(nonce);
+    let auth = Zeroizing::new(mac.finalize().into_bytes().into());
+
+    (encryption, auth)
+}
+
+fn tag(auth_key: &[u8; 32], pieces: &[&[u8]]) -> Blake2bMac<U32> {
+    let mut mac = Blake2bMac::<U32>::new_from_slice(auth_key).expect("valid key length");
+    mac.update(&pae(pieces));
+    mac
+}
+
+pub fn encrypt(seed: &Seed, realm: &str, message: &[u8], footer: &[u8], implicit: &[u8]) -> String {
+    let mut nonce = [0u8; 32];
+    OsRng.fill_bytes(&mut nonce);
+    encrypt_with_nonce(&local_key(seed, realm), &nonce, message, footer, implicit)
+}
+
+fn encrypt_with_nonce(
+    key: &[u8; 32],
+    nonce: &[u8; 32],
+    message: &[u8],
+    footer: &[u8],
+    implicit: &[u8],
+) -> String {
+    let (encryption, auth) = local_keys(key, nonce);
+    let mut ciphertext = message.to_vec();
+    XChaCha20::new(encryption[..32].into(), encryption[32..].into())
+        .apply_keystream(&mut ciphertext);
+
+    let t = tag(
+        &auth,
+        &[LOCAL.as_bytes(), nonce, &ciphertext, footer, implicit],
+    )
+    .finalize()
+    .into_bytes();
+
+    let mut body = nonce.to_vec();
+    body.extend_from_slice(&ciphertext);
+    body.extend_from_slice(&t);
+    assemble(LOCAL, &body, footer)
+}
+
+/// Decrypt a `v4.local` token and return its message. With `footer`,
+/// the token's footer must match it.
+pub fn decrypt(
+    seed: &Seed,
+    realm: &str,
+    token: &str,
+    footer: Option<&[u8]>,
+    implicit: &[u8],
+) -> Result<Zeroizing<Vec<u8>>, Box<dyn std::error::Error>> {
+    decrypt_with_key(&local_key(seed, realm), token, footer, implicit)
+}
+
+fn decrypt_with_key(
+    key: &[u8; 32],
+    token: &str,
+    footer: Option<&[u8]>,
+    implicit: &[u8],
+) -> Result<Zeroizing<Vec<u8>>, Box<dyn std::error::Error>> {
+    let (body, footer) = split(token, LOCAL, footer)?;
+    if body.len() < 64 {
+        return Err("invalid token: too short".into());
+    }
+    let (nonce, rest) = body.split_at(32);
+    let (ciphertext, t) = rest.split_at(rest.len() - 32);
+
+    let (encryption, auth) = local_keys(key, nonce);
+    tag(
+        &auth,
+        &[LOCAL.as_bytes(), nonce, ciphertext, &footer, implicit],
+    )
+    .verify_slice(t)
+    .map_err(|_| "invalid token: authentication failed")?;
+
+    let mut message = Zeroizing::new(ciphertext.to_vec());
+    XChaCha20::new(encryption[..32].into(), encryption[32..].into()).apply_keystream(&mut message);
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::SigningKey;
+
+    // test vectors 4-S-3 and 4-E-5 from the PASETO specification
+    const MESSAGE: &[u8] =
+        br#"{"data":"this is a signed message","exp":"2022-01-01T00:00:00+00:00"}"#;
+    const FOOTER: &[u8] = br#"{"kid":"zVhMiPBP9fRf2snEcT7gFTioeA9COcNy9DfgL1W60haN"}"#;
+    const IMPLICIT: &[u8] = br#"{"test-vector":"4-S-3"}"#;
+
+    #[test]
+    fn public_vector() {
+        let secret =
+            hex::decode("b4cbfb43df4ce210727d953e4a713307fa19bb7d9f85041438d9e11b942a3774")
+                .unwrap();
+        let key = SigningKey::from_bytes(&secret.try_into().unwrap());
+        let signature = key.sign(&pae(&[PUBLIC.as_bytes(), MESSAGE, FOOTER, IMPLICIT]));
+        let mut body = MESSAGE.to_vec();
+        body.extend_from_slice(&signature.to_bytes());
+        let token = assemble(PUBLIC, &body, FOOTER);
+
+        assert_eq!(
+            token,
+            "v4.public.eyJkYXRhIjoidGhpcyBpcyBhIHNpZ25lZCBtZXNzYWdlIiwiZXhwIjoiMjAyMi0wMS0wMVQwMDowMDowMCswMDowMCJ9NPWciuD3d0o5eXJXG5pJy-DiVEoyPYWs1YSTwWHNJq6DZD3je5gf-0M4JR9ipdUSJbIovzmBECeaWmaqcaP0DQ.eyJraWQiOiJ6VmhNaVBCUDlmUmYyc25FY1Q3Z0ZUaW9lQTlDT2NOeTlEZmdMMVc2MGhhTiJ9"
+        );
+        let message = verify(&token, &key.verifying_key(), Some(FOOTER), IMPLICIT).unwrap();
+        assert_eq!(message, MESSAGE);
+        assert!(verify(&token, &key.verifying_key(), None, b"").is_err());
+    }
+
+    #[test]
+    fn local_vector() {
+        let key: [u8; 32] =
+            hex::decode("707172737475767778797a7b7c7d7e7f808182838485868788898a8b8c8d8e8f")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        let nonce: [u8; 32] =
+            hex::decode("df654812bac492663825520ba2f6e67cf5ca5bdc13d4e7507a98cc4c2fcc3ad8")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        let message = br#"{"data":"this is a secret message","exp":"2022-01-01T00:00:00+00:00"}"#;
+
+        let token = encrypt_with_nonce(&key, &nonce, message, FOOTER, b"");
+        assert_eq!(
+            token,
+            "v4.local.32VIErrEkmY4JVILovbmfPXKW9wT1OdQepjMTC_MOtjA4kiqw7_tcaOM5GNEcnTxl60WkwMsYXw6FSNb_UdJPXjpzm0KW9ojM5f4O2mRvE2IcweP-PRdoHjd5-RHCiExR1IK6t4x-RMNXtQNbz7FvFZ_G-lFpk5RG3EOrwDL6CgDqcerSQ.eyJraWQiOiJ6VmhNaVBCUDlmUmYyc25FY1Q3Z0ZUaW9lQTlDT2NOeTlEZmdMMVc2MGhhTiJ9"
+        );
+
+        let decrypted = decrypt_with_key(&key, &token, Some(FOOTER), b"").unwrap();
+        assert_eq!(decrypted.as_slice(), message);
+        assert!(decrypt_with_key(&key, &token, None, b"aud").is_err());
+    }
+
+    #[test]
+    fn realm_tokens_round_trip() {
+        let seed = Seed::from_passphrase("test").unwrap();
+
+        let token = sign(&seed, "realm", b"hello", b"", b"aud");
+        let key = parse_public_key(&public_key(&seed, "realm")).unwrap();
+        assert!(verify(&token, &key, None, b"aud").is_ok());
+
+        let token = encrypt(&seed, "realm", b"secret", b"kid", b"");
+        let message = decrypt(&seed, "realm", &token, Some(b"kid"), b"").unwrap();
+        assert_eq!(message.as_slice(), b"secret");
+        assert!(decrypt(&seed, "other", &token, None, b"").is_err());
+    }
+}
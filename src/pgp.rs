@@ -0,0 +1,319 @@
+use crate::age::x25519_public;
+use crate::seed::Seed;
+use base64::Engine;
+use ed25519_dalek::{Signer, SigningKey};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+
+/// Default key creation time (2024-01-01T00:00:00Z). It is part of the
+/// fingerprint, so it must never depend on the clock.
+pub const DEFAULT_CREATED: u32 = 1_704_067_200;
+
+const ALGO_ECDH: u8 = 18;
+const ALGO_EDDSA: u8 = 22;
+const HASH_SHA256: u8 = 8;
+
+// 1.3.6.1.4.1.11591.15.1
+const OID_ED25519: &[u8] = &[0x2b, 0x06, 0x01, 0x04, 0x01, 0xda, 0x47, 0x0f, 0x01];
+// 1.3.6.1.4.1.3029.1.5.1
+const OID_CV25519: &[u8] = &[0x2b, 0x06, 0x01, 0x04, 0x01, 0x97, 0x55, 0x01, 0x05, 0x01];
+
+const TAG_SIGNATURE: u8 = 2;
+const TAG_SECRET_KEY: u8 = 5;
+const TAG_PUBLIC_KEY: u8 = 6;
+const TAG_SECRET_SUBKEY: u8 = 7;
+const TAG_USER_ID: u8 = 13;
+const TAG_PUBLIC_SUBKEY: u8 = 14;
+
+/// Ed25519 certify/sign primary key plus Cv25519 encryption subkey.
+struct Keys {
+    primary: SigningKey,
+    subkey: Zeroizing<[u8; 32]>,
+    created: u32,
+}
+
+impl Keys {
+    fn derive(seed: &Seed, realm: &str, created: u32) -> Self {
+        let primary = SigningKey::from_bytes(&seed.derive_32(realm, "pgp/sign"));
+        let mut subkey = seed.derive_32(realm, "pgp/encrypt");
+        subkey[0] &= 248;
+        subkey[31] &= 127;
+        subkey[31] |= 64;
+        Self {
+            primary,
+            subkey,
+            created,
+        }
+    }
+
+    fn primary_body(&self) -> Vec<u8> {
+        let mut point = vec![0x40];
+        point.extend_from_slice(self.primary.verifying_key().as_bytes());
+
+        let mut body = vec![4];
+        body.extend_from_slice(&self.created.to_be_bytes());
+        body.push(ALGO_EDDSA);
+        body.push(OID_ED25519.len() as u8);
+        body.extend_from_slice(OID_ED25519);
+        body.extend(mpi(&point));
+        body
+    }
+
+    fn subkey_body(&self) -> Vec<u8> {
+        let mut point = vec![0x40];
+        point.extend_from_slice(&x25519_public(&self.subkey));
+
+        let mut body = vec![4];
+        body.extend_from_slice(&self.created.to_be_bytes());
+        body.push(ALGO_ECDH);
+        body.push(OID_CV25519.len() as u8);
+        body.extend_from_slice(OID_CV25519);
+        body.extend(mpi(&point));
+        // KDF parameters: SHA256, AES128
+        body.extend_from_slice(&[3, 1, HASH_SHA256, 7]);
+        body
+    }
+
+    fn fingerprint(&self) -> [u8; 20] {
+        let mut hasher = Sha1::new();
+        hasher.update(key_hash_prefix(&self.primary_body()));
+        hasher.finalize().into()
+    }
+
+    /// Packets shared by the public and secret exports: user ID and the
+    /// self-signatures binding it and the subkey to the primary key.
+    fn signatures(&self, uid: &str) -> (Vec<u8>, Vec<u8>) {
+        let fingerprint = self.fingerprint();
+        let primary = key_hash_prefix(&self.primary_body());
+
+        // positive certification of the user ID
+        let mut data = primary.clone();
+        data.push(0xb4);
+        data.extend_from_slice(&(uid.len() as u32).to_be_bytes());
+        data.extend_from_slice(uid.as_bytes());
+
+        let mut hashed = self.common_subpackets(&fingerprint);
+        hashed.extend(subpacket(27, &[0x03])); // certify, sign
+        hashed.extend(subpacket(11, &[9, 8, 7])); // AES256, AES192, AES128
+        hashed.extend(subpacket(21, &[10, 8])); // SHA512, SHA256
+        hashed.extend(subpacket(22, &[2, 1, 0])); // ZLIB, ZIP, none
+        hashed.extend(subpacket(30, &[0x01])); // MDC
+        let uid_sig = self.sign(0x13, &hashed, &fingerprint, &data);
+
+        // subkey binding
+        let mut data = primary;
+        data.extend(key_hash_prefix(&self.subkey_body()));
+
+        let mut hashed = self.common_subpackets(&fingerprint);
+        hashed.extend(subpacket(27, &[0x0c])); // encrypt communications and storage
+        let subkey_sig = self.sign(0x18, &hashed, &fingerprint, &data);
+
+        (uid_sig, subkey_sig)
+    }
+
+    fn common_subpackets(&self, fingerprint: &[u8; 20]) -> Vec<u8> {
+        let mut issuer_fpr = vec![4];
+        issuer_fpr.extend_from_slice(fingerprint);
+
+        let mut out = subpacket(2, &self.created.to_be_bytes());
+        out.extend(subpacket(33, &issuer_fpr));
+        out
+    }
+
+    fn sign(&self, sig_type: u8, hashed: &[u8], fingerprint: &[u8; 20], data: &[u8]) -> Vec<u8> {
+        let mut body = vec![4, sig_type, ALGO_EDDSA, HASH_SHA256];
+        body.extend_from_slice(&(hashed.len() as u16).to_be_bytes());
+        body.extend_from_slice(hashed);
+
+        let mut hasher = Sha256::new();
+        hasher.update(data);
+        hasher.update(&body);
+        hasher.update([4, 0xff]);
+        hasher.update((body.len() as u32).to_be_bytes());
+        let digest = hasher.finalize();
+
+        // EdDSA in OpenPGP signs the digest itself
+        let signature = self.primary.sign(&digest);
+
+        let unhashed = subpacket(16, &fingerprint[12..]); // issuer key ID
+        body.extend_from_slice(&(unhashed.len() as u16).to_be_bytes());
+        body.extend(unhashed);
+        body.extend_from_slice(&digest[..2]);
+        body.extend(mpi(signature.r_bytes()));
+        body.extend(mpi(signature.s_bytes()));
+        packet(TAG_SIGNATURE, &body)
+    }
+}
+
+/// Transferable public key, armored for `gpg --import`.
+pub fn derive_public(seed: &Seed, realm: &str, uid: &str, created: u32) -> String {
+    let keys = Keys::derive(seed, realm, created);
+    let (uid_sig, subkey_sig) = keys.signatures(uid);
+
+    let mut out = packet(TAG_PUBLIC_KEY, &keys.primary_body());
+    out.extend(packet(TAG_USER_ID, uid.as_bytes()));
+    out.extend(uid_sig);
+    out.extend(packet(TAG_PUBLIC_SUBKEY, &keys.subkey_body()));
+    out.extend(subkey_sig);
+
+    armor("PGP PUBLIC KEY BLOCK", &out)
+}
+
+/// Transferable secret key (unprotected), armored for `gpg --import`.
+pub fn derive_private(seed: &Seed, realm: &str, uid: &str, created: u32) -> Zeroizing<String> {
+    let keys = Keys::derive(seed, realm, created);
+    let (uid_sig, subkey_sig) = keys.signatures(uid);
+
+    let primary_secret = Zeroizing::new(mpi(keys.primary.as_bytes()));
+
+    // Curve25519 secrets are stored as a big-endian MPI of the reversed scalar
+    let mut reversed = Zeroizing::new(*keys.subkey);
+    reversed.reverse();
+    let subkey_secret = Zeroizing::new(mpi(&reversed[..]));
+
+    let mut out = Zeroizing::new(packet(
+        TAG_SECRET_KEY,
+        &secret_body(keys.primary_body(), &primary_secret),
+    ));
+    out.extend(packet(TAG_USER_ID, uid.as_bytes()));
+    out.extend(uid_sig);
+    out.extend(packet(
+        TAG_SECRET_SUBKEY,
+        &secret_body(keys.subkey_body(), &subkey_secret),
+    ));
+    out.extend(subkey_sig);
+
+    Zeroizing::new(armor("PGP PRIVATE KEY BLOCK", &out))
+}
+
+/// V4 fingerprint of the primary key, as shown by `gpg --fingerprint`.
+pub fn fingerprint(seed: &Seed, realm: &str, created: u32) -> String {
+    hex::encode_upper(Keys::derive(seed, realm, created).fingerprint())
+}
+
+fn secret_body(public_body: Vec<u8>, secret_mpi: &[u8]) -> Zeroizing<Vec<u8>> {
+    let checksum = secret_mpi
+        .iter()
+        .fold(0u16, |sum, &b| sum.wrapping_add(b as u16));
+
+    let mut body = Zeroizing::new(public_body);
+    body.push(0); // not encrypted
+    body.extend_from_slice(secret_mpi);
+    body.extend_from_slice(&checksum.to_be_bytes());
+    body
+}
+
+fn key_hash_prefix(body: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x99];
+    out.extend_from_slice(&(body.len() as u16).to_be_bytes());
+    out.extend_from_slice(body);
+    out
+}
+
+fn mpi(bytes: &[u8]) -> Vec<u8> {
+    let start = bytes.iter().position(|&b| b != 0).unwrap_or(bytes.len());
+    let bytes = &bytes[start..];
+    let bits = match bytes.first() {
+        Some(first) => (bytes.len() - 1) * 8 + (8 - first.leading_zeros() as usize),
+        None => 0,
+    };
+
+    let mut out = (bits as u16).to_be_bytes().to_vec();
+    out.extend_from_slice(bytes);
+    out
+}
+
+fn subpacket(kind: u8, data: &[u8]) -> Vec<u8> {
+    // all our subpackets are short enough for a one-octet length
+    let mut out = vec![(data.len() + 1) as u8, kind];
+    out.extend_from_slice(data);
+    out
+}
+
+fn packet(tag: u8, body: &[u8]) -> Vec<u8> {
+    let mut out = vec![0xc0 | tag];
+    let len = body.len();
+    if len < 192 {
+        out.push(len as u8);
+    } else if len < 8384 {
+        let len = len - 192;
+        out.push(((len >> 8) + 192) as u8);
+        out.push((len & 0xff) as u8);
+    } else {
+        out.push(0xff);
+        out.extend_from_slice(&(len as u32).to_be_bytes());
+    }
+    out.extend_from_slice(body);
+    out
+}
+
+fn armor(kind: &str, data: &[u8]) -> String {
+    let engine = base64::engine::general_purpose::STANDARD;
+    let encoded = engine.encode(data);
+
+    let mut out = format!("-----BEGIN {kind}-----\n\n");
+    for line in encoded.as_bytes().chunks(64) {
+        out.push_str(std::str::from_utf8(line).expect("base64 is ascii"));
+        out.push('\n');
+    }
+    out.push('=');
+    out.push_str(&engine.encode(&crc24(data).to_be_bytes()[1..]));
+    out.push_str(&format!("\n-----END {kind}-----\n"));
+    out
+}
+
+fn crc24(data: &[u8]) -> u32 {
+    let mut crc: u32 = 0xb704ce;
+    for &b in data {
+        crc ^= (b as u32) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x1000000 != 0 {
+                crc ^= 0x1864cfb;
+            }
+        }
+    }
+    crc & 0xffffff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mpi_strips_leading_zeros() {
+        assert_eq!(mpi(&[0x00, 0x01]), vec![0x00, 0x01, 0x01]);
+        assert_eq!(mpi(&[0x40, 0xff]), vec![0x00, 0x0f, 0x40, 0xff]);
+    }
+
+    #[test]
+    fn crc24_known_value() {
+        // CRC-24/OPENPGP check value for "123456789"
+        assert_eq!(crc24(b"123456789"), 0x21cf02);
+    }
+
+    #[test]
+    fn fingerprint_stable_per_created() {
+        let seed = Seed::from_passphrase("test").unwrap();
+
+        let fp1 = fingerprint(&seed, "realm", DEFAULT_CREATED);
+        let fp2 = fingerprint(&seed, "realm", DEFAULT_CREATED);
+        let fp3 = fingerprint(&seed, "realm", DEFAULT_CREATED + 1);
+
+        assert_eq!(fp1, fp2);
+        assert_ne!(fp1, fp3);
+        assert_eq!(fp1.len(), 40);
+    }
+
+    #[test]
+    fn public_key_deterministic() {
+        let seed = Seed::from_passphrase("test").unwrap();
+
+        let k1 = derive_public(&seed, "realm", "Test <t@example.com>", DEFAULT_CREATED);
+        let k2 = derive_public(&seed, "realm", "Test <t@example.com>", DEFAULT_CREATED);
+
+        assert_eq!(k1, k2);
+        assert!(k1.starts_with("-----BEGIN PGP PUBLIC KEY BLOCK-----"));
+    }
+}
@@ -0,0 +1,191 @@
+//! Deterministic RSA key generation.
+//!
+//! The prime search is pinned here instead of delegating to
+//! `rsa::RsaPrivateKey::new`, whose algorithm may change between releases.
+//! Changing anything in this file changes every derived RSA key:
+//!
+//! 1. The DRBG is ChaCha20 with key `Seed::derive_32(realm, label)` and an
+//!    all-zero nonce; its keystream is consumed in order.
+//! 2. A candidate is the next `bits / 16` bytes, big-endian, with the top two
+//!    bits and the low bit set.
+//! 3. Candidates divisible by a prime below 2000, or equal to 1 mod 65537,
+//!    are skipped; the rest must pass Miller-Rabin for the first 20 primes.
+//! 4. `p` is the first prime, `q` the next one at least 2^(bits/2 - 100)
+//!    away from `p`. e = 65537 and d = e^-1 mod lcm(p-1, q-1).
+
+use crate::seed::Seed;
+use ::rsa::{BigUint, RsaPrivateKey};
+use chacha20::cipher::{KeyIvInit, StreamCipher};
+use chacha20::ChaCha20;
+use zeroize::Zeroizing;
+
+pub const SIZES: [usize; 3] = [2048, 3072, 4096];
+
+const PUBLIC_EXPONENT: u32 = 65537;
+const SIEVE_LIMIT: u32 = 2000;
+const MILLER_RABIN_BASES: usize = 20;
+
+pub fn generate(
+    seed: &Seed,
+    realm: &str,
+    label: &str,
+    bits: usize,
+) -> Result<RsaPrivateKey, Box<dyn std::error::Error>> {
+    if !SIZES.contains(&bits) {
+        return Err(format!("unsupported RSA key size {bits}: use 2048, 3072 or 4096").into());
+    }
+
+    let key = seed.derive_32(realm, label);
+    let mut drbg = ChaCha20::new(key.as_ref().into(), &[0u8; 12].into());
+    let small_primes = small_primes(SIEVE_LIMIT);
+
+    let p = next_prime(&mut drbg, bits / 2, &small_primes);
+    let min_distance = BigUint::from(1u32) << (bits / 2 - 100);
+    let q = loop {
+        let q = next_prime(&mut drbg, bits / 2, &small_primes);
+        let distance = if p > q { &p - &q } else { &q - &p };
+        if distance > min_distance {
+            break q;
+        }
+    };
+
+    Ok(RsaPrivateKey::from_p_q(
+        p,
+        q,
+        BigUint::from(PUBLIC_EXPONENT),
+    )?)
+}
+
+fn next_prime(drbg: &mut ChaCha20, bits: usize, small_primes: &[u32]) -> BigUint {
+    let mut bytes = Zeroizing::new(vec![0u8; bits / 8]);
+    loop {
+        bytes.fill(0);
+        drbg.apply_keystream(&mut bytes);
+        bytes[0] |= 0xc0;
+        *bytes.last_mut().expect("non-empty") |= 1;
+
+        if small_primes.iter().any(|&p| rem_u32(&bytes, p) == 0) {
+            continue;
+        }
+        if rem_u32(&bytes, PUBLIC_EXPONENT) == 1 {
+            continue;
+        }
+
+        let candidate = BigUint::from_bytes_be(&bytes);
+        if miller_rabin(&candidate, &small_primes[..MILLER_RABIN_BASES]) {
+            return candidate;
+        }
+    }
+}
+
+fn rem_u32(be_bytes: &[u8], m: u32) -> u32 {
+    be_bytes
+        .iter()
+        .fold(0u64, |r, &b| (r * 256 + b as u64) % m as u64) as u32
+}
+
+fn small_primes(limit: u32) -> Vec<u32> {
+    let mut composite = vec![false; limit as usize];
+    let mut primes = Vec::new();
+    for i in 2..limit as usize {
+        if !composite[i] {
+            primes.push(i as u32);
+            for j in (i * i..limit as usize).step_by(i) {
+                composite[j] = true;
+            }
+        }
+    }
+    primes
+}
+
+/// Miller-Rabin with fixed bases; `n` is odd and larger than every base.
+fn miller_rabin(n: &BigUint, bases: &[u32]) -> bool {
+    let one = BigUint::from(1u32);
+    let n_minus_one = n - &one;
+
+    // n - 1 = d * 2^s with d odd
+    let mut s = 0;
+    for byte in n_minus_one.to_bytes_le() {
+        if byte != 0 {
+            s += byte.trailing_zeros() as usize;
+            break;
+        }
+        s += 8;
+    }
+    let d = &n_minus_one >> s;
+
+    'bases: for &base in bases {
+        let mut x = BigUint::from(base).modpow(&d, n);
+        if x == one || x == n_minus_one {
+            continue;
+        }
+        for _ in 1..s {
+            x = x.modpow(&BigUint::from(2u32), n);
+            if x == n_minus_one {
+                continue 'bases;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::rsa::traits::PublicKeyParts;
+    use sha2::{Digest, Sha256};
+
+    #[test]
+    fn miller_rabin_known_values() {
+        let bases = &small_primes(100)[..MILLER_RABIN_BASES];
+        // 2^127 - 1 is prime, 2^128 + 1 is not
+        let m127 = (BigUint::from(1u32) << 127) - BigUint::from(1u32);
+        let f7 = (BigUint::from(1u32) << 128) + BigUint::from(1u32);
+
+        assert!(miller_rabin(&m127, bases));
+        assert!(!miller_rabin(&f7, bases));
+        // Carmichael number
+        assert!(!miller_rabin(&BigUint::from(561u32), bases));
+    }
+
+    fn modulus_digest(bits: usize) -> String {
+        let seed = Seed::from_bytes(&[0xa5; 32]).unwrap();
+        let key = generate(&seed, "realm", &format!("ssh/rsa/{bits}"), bits).unwrap();
+
+        assert_eq!(key.n().bits(), bits);
+        assert_eq!(key.e(), &BigUint::from(65537u32));
+        hex::encode(Sha256::digest(key.n().to_bytes_be()))
+    }
+
+    #[test]
+    fn rsa_2048_vector() {
+        assert_eq!(
+            modulus_digest(2048),
+            "5b3f02728b42bd9a84551c33f8597b349d09732ba1e8e3f7138208436262a7a3"
+        );
+    }
+
+    #[test]
+    fn rsa_3072_vector() {
+        assert_eq!(
+            modulus_digest(3072),
+            "86398e61886551d3c262bf292ef7f5340d020f4846e24a0103340a7ffb35e729"
+        );
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore = "slow without optimizations")]
+    fn rsa_4096_vector() {
+        assert_eq!(
+            modulus_digest(4096),
+            "f93e5f5cbb7121f115897aa5bf38ee60b111526f0a0df4a5ec25f7942325d1c9"
+        );
+    }
+
+    #[test]
+    fn unsupported_size_rejected() {
+        let seed = Seed::from_bytes(&[0xa5; 32]).unwrap();
+        assert!(generate(&seed, "realm", "x", 1024).is_err());
+    }
+}
@@ -1,3 +1,4 @@
+use crate::harden;
 use hkdf::Hkdf;
 use keyring::Entry;
 use scrypt::{scrypt, Params};
@@ -9,23 +10,52 @@ const SCRYPT_R: u32 = 8;
 const SCRYPT_P: u32 = 1;
 const VERSION: &str = "v1";
 
+/// Seed as stored: 32 random bytes or a passphrase.
+pub type SeedBytes = Zeroizing<Vec<u8>>;
+
 pub struct Seed {
-    master: Zeroizing<[u8; 32]>,
+    // boxed so the locked memory never moves
+    master: Box<Zeroizing<[u8; 32]>>,
 }
 
 pub enum SeedSource {
     EnvFile(PathBuf),
     Keyring,
     DefaultFile(PathBuf),
+    EncryptedFile(PathBuf),
+}
+
+/// Storage locations `1seed seed move` can move the seed between.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Backend {
+    Keyring,
+    File,
+    EncryptedFile,
+}
+
+impl Backend {
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Keyring => "keyring",
+            Backend::File => "~/.1seed",
+            Backend::EncryptedFile => "~/.1seed.age",
+        }
+    }
 }
 
 impl Seed {
-    fn default_file_path() -> PathBuf {
+    pub fn default_file_path() -> PathBuf {
         dirs::home_dir()
             .unwrap_or_else(|| PathBuf::from("."))
             .join(".1seed")
     }
 
+    pub fn encrypted_file_path() -> PathBuf {
+        dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(".1seed.age")
+    }
+
     pub fn from_passphrase(passphrase: &str) -> Result<Self, Box<dyn std::error::Error>> {
         let scrypt_n = if std::env::var("ONESEED_TEST_MODE").is_ok() {
             12
@@ -33,14 +63,20 @@ impl Seed {
             20
         };
         let params = Params::new(scrypt_n, SCRYPT_R, SCRYPT_P, 32)?;
-        let mut master = Zeroizing::new([0u8; 32]);
-        scrypt(passphrase.as_bytes(), b"1seed", &params, master.as_mut())?;
+        let mut master = Self::new_master();
+        scrypt(passphrase.as_bytes(), b"1seed", &params, &mut master[..])?;
         Ok(Self { master })
     }
 
-    fn from_bytes(bytes: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+    fn new_master() -> Box<Zeroizing<[u8; 32]>> {
+        let master = Box::new(Zeroizing::new([0u8; 32]));
+        harden::lock(&master[..]);
+        master
+    }
+
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
         if bytes.len() >= 32 && bytes.iter().any(|&b| !(32..=127).contains(&b)) {
-            let mut master = Zeroizing::new([0u8; 32]);
+            let mut master = Self::new_master();
             master.copy_from_slice(&bytes[..32]);
             Ok(Self { master })
         } else {
@@ -50,71 +86,262 @@ impl Seed {
         }
     }
 
-    pub fn from_file(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
-        let bytes = std::fs::read(path)?;
-        Self::from_bytes(&bytes)
+    /// Refuse seed files that other users could read or replace, like
+    /// OpenSSH does for private keys.
+    pub fn check_permissions(path: &Path) -> Result<(), String> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+
+            let meta = std::fs::metadata(path).map_err(|e| format!("{}: {e}", path.display()))?;
+            let uid = unsafe { libc::getuid() };
+            if meta.uid() != uid {
+                return Err(format!(
+                    "seed file {} is owned by uid {}, not by you (uid {uid})",
+                    path.display(),
+                    meta.uid()
+                ));
+            }
+
+            let mode = meta.mode() & 0o777;
+            if mode & 0o077 != 0 {
+                return Err(format!(
+                    "seed file {} is accessible by others (mode {mode:04o}), run '1seed fix-perms'",
+                    path.display()
+                ));
+            }
+        }
+
+        #[cfg(not(unix))]
+        let _ = path;
+
+        Ok(())
     }
 
-    pub fn load() -> Result<(Self, SeedSource), Box<dyn std::error::Error>> {
+    fn read_file_checked(
+        path: &Path,
+        allow_insecure: bool,
+    ) -> Result<Zeroizing<Vec<u8>>, Box<dyn std::error::Error>> {
+        if !allow_insecure {
+            Self::check_permissions(path)?;
+        }
+        Ok(harden::read_all(std::fs::File::open(path)?)?)
+    }
+
+    pub fn load(allow_insecure: bool) -> Result<(Self, SeedSource), Box<dyn std::error::Error>> {
+        let (bytes, source) = Self::load_raw(allow_insecure)?;
+        Ok((Self::from_bytes(&bytes)?, source))
+    }
+
+    /// Load the seed exactly as stored, before any passphrase stretching.
+    pub fn load_raw(
+        allow_insecure: bool,
+    ) -> Result<(SeedBytes, SeedSource), Box<dyn std::error::Error>> {
         if let Ok(path_str) = std::env::var("SEED_FILE") {
             let path = PathBuf::from(path_str);
-            return Ok((Self::from_file(&path)?, SeedSource::EnvFile(path)));
+            return Ok((
+                Self::read_file_checked(&path, allow_insecure)?,
+                SeedSource::EnvFile(path),
+            ));
         }
 
         let use_file_only = std::env::var("SEED_NO_KEYRING").is_ok();
 
         if !use_file_only {
-            if let Ok(seed) = Self::from_keyring() {
-                return Ok((seed, SeedSource::Keyring));
+            if let Ok(bytes) = Self::read_keyring() {
+                return Ok((bytes, SeedSource::Keyring));
             }
         }
 
         let default_file = Self::default_file_path();
         if default_file.exists() {
             return Ok((
-                Self::from_file(&default_file)?,
+                Self::read_file_checked(&default_file, allow_insecure)?,
                 SeedSource::DefaultFile(default_file),
             ));
         }
 
+        let encrypted_file = Self::encrypted_file_path();
+        if encrypted_file.exists() {
+            let passphrase = Self::prompt("seed passphrase")?;
+            let bytes =
+                Self::read_backend(Backend::EncryptedFile, Some(&passphrase), allow_insecure)?;
+            return Ok((bytes, SeedSource::EncryptedFile(encrypted_file)));
+        }
+
         Err("no seed found, run '1seed init --generate'".into())
     }
 
-    fn from_keyring() -> Result<Self, Box<dyn std::error::Error>> {
+    fn prompt(prompt: &str) -> Result<Zeroizing<String>, Box<dyn std::error::Error>> {
+        use std::io::Write;
+
+        eprint!("{prompt}: ");
+        std::io::stderr().flush()?;
+        Ok(Zeroizing::new(rpassword::read_password()?))
+    }
+
+    /// Probe the keyring without loading the seed: `Ok(false)` means the
+    /// backend works but holds no entry.
+    pub fn keyring_probe() -> Result<bool, keyring::Error> {
+        match Entry::new("1seed", "master-seed").and_then(|e| e.get_secret()) {
+            Ok(_) => Ok(true),
+            Err(keyring::Error::NoEntry) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn read_keyring() -> Result<Zeroizing<Vec<u8>>, Box<dyn std::error::Error>> {
         let entry = Entry::new("1seed", "master-seed")?;
-        let bytes = entry.get_secret()?;
-        Self::from_bytes(&bytes)
+        Ok(Zeroizing::new(entry.get_secret()?))
     }
 
     pub fn store(data: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
         let use_file_only = std::env::var("SEED_NO_KEYRING").is_ok();
 
         if use_file_only {
-            let path = Self::default_file_path();
-            std::fs::write(&path, data)?;
-            #[cfg(unix)]
-            {
-                use std::fs::Permissions;
-                use std::os::unix::fs::PermissionsExt;
-                std::fs::set_permissions(&path, Permissions::from_mode(0o600))?;
-            }
-            return Ok(());
+            return write_private(&Self::default_file_path(), data);
         }
 
         match Entry::new("1seed", "master-seed").and_then(|e| e.set_secret(data)) {
             Ok(()) => Ok(()),
-            Err(_) => {
-                let path = Self::default_file_path();
-                std::fs::write(&path, data)?;
-                #[cfg(unix)]
-                {
-                    use std::fs::Permissions;
-                    use std::os::unix::fs::PermissionsExt;
-                    std::fs::set_permissions(&path, Permissions::from_mode(0o600))?;
-                }
-                Ok(())
+            Err(_) => write_private(&Self::default_file_path(), data),
+        }
+    }
+
+    fn read_backend(
+        backend: Backend,
+        passphrase: Option<&str>,
+        allow_insecure: bool,
+    ) -> Result<Zeroizing<Vec<u8>>, Box<dyn std::error::Error>> {
+        match backend {
+            Backend::Keyring => Self::read_keyring(),
+            Backend::File => Self::read_file_checked(&Self::default_file_path(), allow_insecure),
+            Backend::EncryptedFile => {
+                let ciphertext =
+                    Self::read_file_checked(&Self::encrypted_file_path(), allow_insecure)?;
+                decrypt_with_passphrase(&ciphertext, passphrase.ok_or("passphrase required")?)
+            }
+        }
+    }
+
+    fn write_backend(
+        backend: Backend,
+        data: &[u8],
+        passphrase: Option<&str>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match backend {
+            Backend::Keyring => Ok(Entry::new("1seed", "master-seed")?.set_secret(data)?),
+            Backend::File => write_private(&Self::default_file_path(), data),
+            Backend::EncryptedFile => {
+                let ciphertext =
+                    encrypt_with_passphrase(data, passphrase.ok_or("passphrase required")?)?;
+                write_private(&Self::encrypted_file_path(), &ciphertext)
+            }
+        }
+    }
+
+    fn backend_holds_seed(backend: Backend) -> bool {
+        match backend {
+            Backend::Keyring => Self::keyring_probe().unwrap_or(false),
+            Backend::File => Self::default_file_path().exists(),
+            Backend::EncryptedFile => Self::encrypted_file_path().exists(),
+        }
+    }
+
+    fn remove_backend(backend: Backend) -> Result<(), Box<dyn std::error::Error>> {
+        match backend {
+            Backend::Keyring => Ok(Entry::new("1seed", "master-seed")?.delete_credential()?),
+            Backend::File => Ok(std::fs::remove_file(Self::default_file_path())?),
+            Backend::EncryptedFile => Ok(std::fs::remove_file(Self::encrypted_file_path())?),
+        }
+    }
+
+    /// Move the stored seed to `target`. The old copy is removed only after
+    /// the new one reads back with the same fingerprint.
+    pub fn move_to(
+        target: Backend,
+        passphrase: Option<&str>,
+        allow_insecure: bool,
+    ) -> Result<(Backend, String), Box<dyn std::error::Error>> {
+        if target == Backend::Keyring && std::env::var("SEED_NO_KEYRING").is_ok() {
+            return Err("SEED_NO_KEYRING is set, unset it to use the keyring".into());
+        }
+
+        let (data, source) = Self::load_raw(allow_insecure)?;
+        let from = match source {
+            SeedSource::EnvFile(_) => {
+                return Err("seed comes from SEED_FILE, use 'init --from-file' to import it".into())
+            }
+            SeedSource::Keyring => Backend::Keyring,
+            SeedSource::DefaultFile(_) => Backend::File,
+            SeedSource::EncryptedFile(_) => Backend::EncryptedFile,
+        };
+
+        if from == target {
+            return Err(format!("seed is already in {}", target.name()).into());
+        }
+        if Self::backend_holds_seed(target) {
+            return Err(format!("{} already holds a seed, remove it first", target.name()).into());
+        }
+
+        let fingerprint = Self::from_bytes(&data)?.fingerprint();
+
+        Self::write_backend(target, &data, passphrase)?;
+
+        let stored = Self::read_backend(target, passphrase, allow_insecure)
+            .and_then(|bytes| Self::from_bytes(&bytes));
+        match stored {
+            Ok(seed) if seed.fingerprint() == fingerprint => {}
+            _ => {
+                let _ = Self::remove_backend(target);
+                return Err(format!(
+                    "copy in {} did not verify, kept seed in {}",
+                    target.name(),
+                    from.name()
+                )
+                .into());
             }
         }
+
+        Self::remove_backend(from)?;
+        Ok((from, fingerprint))
+    }
+
+    /// Restrict existing seed files to 0600, returning the paths changed.
+    pub fn fix_permissions() -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+        let mut fixed = vec![];
+
+        #[cfg(unix)]
+        for path in std::env::var_os("SEED_FILE")
+            .map(PathBuf::from)
+            .into_iter()
+            .chain([Self::default_file_path()])
+        {
+            use std::fs::Permissions;
+            use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+            let Ok(meta) = std::fs::metadata(&path) else {
+                continue;
+            };
+
+            let uid = unsafe { libc::getuid() };
+            if meta.uid() != uid {
+                return Err(format!(
+                    "{} is owned by uid {}, run: sudo chown {uid} {}",
+                    path.display(),
+                    meta.uid(),
+                    path.display()
+                )
+                .into());
+            }
+
+            if meta.mode() & 0o077 != 0 {
+                std::fs::set_permissions(&path, Permissions::from_mode(0o600))?;
+                fixed.push(path);
+            }
+        }
+
+        Ok(fixed)
     }
 
     pub fn remove() -> Result<(), Box<dyn std::error::Error>> {
@@ -126,10 +353,11 @@ impl Seed {
             }
         }
 
-        let default_file = Self::default_file_path();
-        if default_file.exists() {
-            std::fs::remove_file(&default_file)?;
-            removed_any = true;
+        for path in [Self::default_file_path(), Self::encrypted_file_path()] {
+            if path.exists() {
+                std::fs::remove_file(&path)?;
+                removed_any = true;
+            }
         }
 
         if removed_any {
@@ -154,13 +382,23 @@ impl Seed {
             return true;
         }
 
-        Self::default_file_path().exists()
+        Self::default_file_path().exists() || Self::encrypted_file_path().exists()
+    }
+
+    /// Short identifier for the master seed, independent of realm.
+    pub fn fingerprint(&self) -> String {
+        let hk = Hkdf::<Sha256>::new(None, &self.master[..]);
+        let mut output = [0u8; 8];
+        hk.expand(format!("{VERSION}/fingerprint").as_bytes(), &mut output)
+            .expect("length should be valid");
+        hex::encode(output)
     }
 
     pub fn derive(&self, realm: &str, key_type: &str, length: usize) -> Zeroizing<Vec<u8>> {
         let path = format!("{VERSION}/{realm}/{key_type}");
-        let hk = Hkdf::<Sha256>::new(None, self.master.as_ref());
+        let hk = Hkdf::<Sha256>::new(None, &self.master[..]);
         let mut output = Zeroizing::new(vec![0u8; length]);
+        harden::lock(&output);
         hk.expand(path.as_bytes(), output.as_mut_slice())
             .expect("length should be valid");
         output
@@ -174,6 +412,62 @@ impl Seed {
     }
 }
 
+/// Write a file that is never readable by others, not even briefly.
+pub fn write_private(path: &Path, data: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(path)?;
+
+    // mode only applies on creation, so tighten pre-existing files before
+    // the secret goes in
+    #[cfg(unix)]
+    {
+        use std::fs::Permissions;
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(Permissions::from_mode(0o600))?;
+    }
+
+    file.write_all(data)?;
+    Ok(())
+}
+
+fn encrypt_with_passphrase(
+    data: &[u8],
+    passphrase: &str,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    use std::io::Write;
+
+    let encryptor = ::age::Encryptor::with_user_passphrase(passphrase.to_string().into());
+    let mut ciphertext = vec![];
+    let mut writer = encryptor.wrap_output(&mut ciphertext)?;
+    writer.write_all(data)?;
+    writer.finish()?;
+    Ok(ciphertext)
+}
+
+fn decrypt_with_passphrase(
+    ciphertext: &[u8],
+    passphrase: &str,
+) -> Result<Zeroizing<Vec<u8>>, Box<dyn std::error::Error>> {
+    let decryptor = match ::age::Decryptor::new(ciphertext)? {
+        ::age::Decryptor::Passphrase(d) => d,
+        ::age::Decryptor::Recipients(_) => {
+            return Err("seed file is not passphrase-encrypted".into())
+        }
+    };
+
+    let reader = decryptor.decrypt(&passphrase.to_string().into(), None)?;
+    Ok(harden::read_all(reader)?)
+}
+
 impl Drop for Seed {
     fn drop(&mut self) {
         self.master.zeroize();
@@ -214,4 +508,42 @@ mod tests {
 
         assert_ne!(key1.as_slice(), key2.as_slice());
     }
+
+    #[test]
+    fn fingerprint_tracks_seed() {
+        let seed1 = Seed::from_passphrase("test").unwrap();
+        let seed2 = Seed::from_passphrase("test").unwrap();
+        let other = Seed::from_passphrase("other").unwrap();
+
+        assert_eq!(seed1.fingerprint(), seed2.fingerprint());
+        assert_ne!(seed1.fingerprint(), other.fingerprint());
+        assert_eq!(seed1.fingerprint().len(), 16);
+    }
+
+    #[test]
+    fn encrypted_seed_roundtrip() {
+        let data = b"seed bytes";
+        let ciphertext = encrypt_with_passphrase(data, "pass").unwrap();
+
+        let plaintext = decrypt_with_passphrase(&ciphertext, "pass").unwrap();
+        assert_eq!(plaintext.as_slice(), data);
+        assert!(decrypt_with_passphrase(&ciphertext, "wrong").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_private_tightens_existing_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("seed");
+        std::fs::write(&path, b"old").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        write_private(&path, b"secret").unwrap();
+
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(std::fs::read(&path).unwrap(), b"secret");
+    }
 }
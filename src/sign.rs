@@ -1,42 +1,188 @@
+use crate::ec::{self, Algorithm};
 use crate::seed::Seed;
+use base64::Engine;
+use blake2::{Blake2b512, Digest};
+use ed25519_dalek::pkcs8::spki::der::pem::LineEnding;
+use ed25519_dalek::pkcs8::{EncodePrivateKey, EncodePublicKey};
 use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use p256::ecdsa::signature::{DigestSigner, DigestVerifier};
+use sha2::{Sha256, Sha512};
 use std::io::Read;
 use std::path::Path;
+use zeroize::Zeroizing;
+
+#[derive(Clone, Copy)]
+pub enum KeyFormat {
+    /// Raw key bytes (Ed25519) or compressed SEC1 point (P-256)
+    Base64,
+    /// SPKI for public keys, PKCS#8 for private keys
+    Pem,
+    Jwk,
+}
+
+enum Key {
+    Ed25519(SigningKey),
+    P256(p256::ecdsa::SigningKey),
+}
+
+fn derive_key(seed: &Seed, realm: &str, alg: Algorithm) -> Key {
+    match alg {
+        Algorithm::Ed25519 => Key::Ed25519(signing_key(seed, realm)),
+        Algorithm::P256 => Key::P256(ec::derive_p256(seed, realm, "sign/p256")),
+    }
+}
+
+/// The realm's Ed25519 signing key, shared by the token and signature
+/// formats built on `sign`.
+pub fn signing_key(seed: &Seed, realm: &str) -> SigningKey {
+    SigningKey::from_bytes(&seed.derive_32(realm, "sign"))
+}
+
+/// Key number for the minisign and signify formats. Both tools pick it at
+/// random; ours comes from the public key so it is the same wherever the
+/// seed is.
+pub fn key_id(key: &VerifyingKey) -> [u8; 8] {
+    Blake2b512::digest(key.as_bytes())[..8]
+        .try_into()
+        .expect("eight bytes")
+}
 
 pub fn derive_public(seed: &Seed, realm: &str) -> String {
-    let raw = seed.derive_32(realm, "sign");
-    let signing_key = SigningKey::from_bytes(&raw);
-    let verifying_key = signing_key.verifying_key();
+    public_key(seed, realm, Algorithm::Ed25519, KeyFormat::Base64)
+}
+
+pub fn public_key(seed: &Seed, realm: &str, alg: Algorithm, format: KeyFormat) -> String {
+    let b64 = base64::engine::general_purpose::STANDARD;
 
-    use base64::Engine;
-    base64::engine::general_purpose::STANDARD.encode(verifying_key.as_bytes())
+    match (derive_key(seed, realm, alg), format) {
+        (Key::Ed25519(k), KeyFormat::Base64) => b64.encode(k.verifying_key().as_bytes()),
+        (Key::Ed25519(k), KeyFormat::Pem) => k
+            .verifying_key()
+            .to_public_key_pem(LineEnding::LF)
+            .expect("valid key"),
+        (Key::Ed25519(k), KeyFormat::Jwk) => ec::ed25519_jwk(&k, false).to_string(),
+        (Key::P256(k), KeyFormat::Base64) => {
+            b64.encode(k.verifying_key().to_encoded_point(true).as_bytes())
+        }
+        (Key::P256(k), KeyFormat::Pem) => k
+            .verifying_key()
+            .to_public_key_pem(LineEnding::LF)
+            .expect("valid key"),
+        (Key::P256(k), KeyFormat::Jwk) => ec::p256_jwk(&k, false).to_string(),
+    }
 }
 
+pub fn private_key(
+    seed: &Seed,
+    realm: &str,
+    alg: Algorithm,
+    format: KeyFormat,
+) -> Result<Zeroizing<String>, Box<dyn std::error::Error>> {
+    let b64 = base64::engine::general_purpose::STANDARD;
+
+    Ok(match (derive_key(seed, realm, alg), format) {
+        (Key::Ed25519(k), KeyFormat::Base64) => Zeroizing::new(b64.encode(k.as_bytes())),
+        (Key::Ed25519(k), KeyFormat::Pem) => k.to_pkcs8_pem(LineEnding::LF)?,
+        (Key::Ed25519(k), KeyFormat::Jwk) => ec::ed25519_jwk(&k, true),
+        (Key::P256(k), KeyFormat::Base64) => Zeroizing::new(b64.encode(k.to_bytes())),
+        (Key::P256(k), KeyFormat::Pem) => k.to_pkcs8_pem(LineEnding::LF)?,
+        (Key::P256(k), KeyFormat::Jwk) => ec::p256_jwk(&k, true),
+    })
+}
+
+const PREHASH_ED25519_ONLY: &str = "--prehash is only used with Ed25519 keys";
+
+/// Ed25519 signature, or P-256 ECDSA over SHA-256 as fixed-size r || s
+/// (the JWS ES256 encoding); both are 64 bytes and deterministic.
+///
+/// P-256 hashes the input as it is read. Ed25519 has to hold the whole
+/// input unless `prehash` selects Ed25519ph (RFC 8032), which signs a
+/// streamed SHA-512 instead; its signatures only verify as Ed25519ph.
 pub fn sign(
     seed: &Seed,
     realm: &str,
+    alg: Algorithm,
     input: Option<&Path>,
+    prehash: bool,
 ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
-    let raw = seed.derive_32(realm, "sign");
-    let signing_key = SigningKey::from_bytes(&raw);
-
-    let data = read_input(input)?;
-    let signature = signing_key.sign(&data);
+    Ok(match derive_key(seed, realm, alg) {
+        Key::Ed25519(k) if prehash => k
+            .sign_prehashed(hash_input(input, Sha512::new())?, None)?
+            .to_bytes()
+            .to_vec(),
+        Key::Ed25519(k) => k.sign(&read_input(input)?).to_bytes().to_vec(),
+        Key::P256(_) if prehash => return Err(PREHASH_ED25519_ONLY.into()),
+        Key::P256(k) => {
+            let signature: p256::ecdsa::Signature =
+                k.try_sign_digest(hash_input(input, Sha256::new())?)?;
+            signature.to_bytes().to_vec()
+        }
+    })
+}
 
-    Ok(signature.to_bytes().to_vec())
+pub fn sign_bytes(seed: &Seed, realm: &str, alg: Algorithm, data: &[u8]) -> Vec<u8> {
+    match derive_key(seed, realm, alg) {
+        Key::Ed25519(k) => k.sign(data).to_bytes().to_vec(),
+        Key::P256(k) => {
+            let signature: p256::ecdsa::Signature = k.sign(data);
+            signature.to_bytes().to_vec()
+        }
+    }
 }
 
+/// Verify against a base64 public key as printed by `sign pub`; the key
+/// length tells Ed25519 (32 bytes) from P-256 (33 or 65 bytes). `prehash`
+/// expects an Ed25519ph signature, as `sign` makes with it.
 pub fn verify(
     pubkey_b64: &str,
     signature: &[u8],
     input: Option<&Path>,
+    prehash: bool,
 ) -> Result<bool, Box<dyn std::error::Error>> {
-    use base64::Engine;
-
     let pubkey_bytes = base64::engine::general_purpose::STANDARD.decode(pubkey_b64)?;
+
+    if pubkey_bytes.len() == 33 || pubkey_bytes.len() == 65 {
+        if prehash {
+            return Err(PREHASH_ED25519_ONLY.into());
+        }
+        let verifying_key = p256::ecdsa::VerifyingKey::from_sec1_bytes(&pubkey_bytes)
+            .map_err(|_| "invalid P-256 public key")?;
+        let signature =
+            p256::ecdsa::Signature::from_slice(signature).map_err(|_| "invalid signature")?;
+        let digest = hash_input(input, Sha256::new())?;
+        return Ok(verifying_key.verify_digest(digest, &signature).is_ok());
+    }
+    if !prehash {
+        return verify_bytes(&pubkey_bytes, signature, &read_input(input)?);
+    }
+
     let pubkey_array: [u8; 32] = pubkey_bytes
         .try_into()
         .map_err(|_| "invalid public key length")?;
+    let sig_array: [u8; 64] = signature
+        .try_into()
+        .map_err(|_| "invalid signature length")?;
+    let verifying_key = VerifyingKey::from_bytes(&pubkey_array)?;
+    let digest = hash_input(input, Sha512::new())?;
+    Ok(verifying_key
+        .verify_prehashed(digest, None, &Signature::from_bytes(&sig_array))
+        .is_ok())
+}
+
+pub fn verify_bytes(
+    pubkey: &[u8],
+    signature: &[u8],
+    data: &[u8],
+) -> Result<bool, Box<dyn std::error::Error>> {
+    if pubkey.len() == 33 || pubkey.len() == 65 {
+        let verifying_key = p256::ecdsa::VerifyingKey::from_sec1_bytes(pubkey)
+            .map_err(|_| "invalid P-256 public key")?;
+        let signature =
+            p256::ecdsa::Signature::from_slice(signature).map_err(|_| "invalid signature")?;
+        return Ok(verifying_key.verify(data, &signature).is_ok());
+    }
+
+    let pubkey_array: [u8; 32] = pubkey.try_into().map_err(|_| "invalid public key length")?;
 
     let sig_array: [u8; 64] = signature
         .try_into()
@@ -45,18 +191,29 @@ pub fn verify(
     let verifying_key = VerifyingKey::from_bytes(&pubkey_array)?;
     let signature = Signature::from_bytes(&sig_array);
 
-    let data = read_input(input)?;
+    Ok(verifying_key.verify(data, &signature).is_ok())
+}
 
-    Ok(verifying_key.verify(&data, &signature).is_ok())
+/// Feed the file (default: stdin) through `hasher` a buffer at a time, so
+/// the input never has to fit in memory.
+pub fn hash_input<D: Digest + std::io::Write>(
+    path: Option<&Path>,
+    mut hasher: D,
+) -> Result<D, Box<dyn std::error::Error>> {
+    match path {
+        Some(p) => std::io::copy(&mut std::fs::File::open(p)?, &mut hasher)?,
+        None => std::io::copy(&mut std::io::stdin().lock(), &mut hasher)?,
+    };
+    Ok(hasher)
 }
 
-fn read_input(path: Option<&Path>) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+pub fn read_input(path: Option<&Path>) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
     match path {
         Some(p) => Ok(std::fs::read(p)?),
         None => {
-            let mut buf = vec![];
-            std::io::stdin().read_to_end(&mut buf)?;
-            Ok(buf)
+            let mut data = Vec::new();
+            std::io::stdin().lock().read_to_end(&mut data)?;
+            Ok(data)
         }
     }
 }
@@ -0,0 +1,104 @@
+//! OpenBSD signify public keys and signatures over the realm's Ed25519
+//! signing key. Public keys share their layout with minisign, so they are
+//! parsed with `minisign::PublicKey`.
+
+use crate::minisign::PublicKey;
+use crate::seed::Seed;
+use crate::sign;
+use base64::Engine;
+use ed25519_dalek::{Signature, Signer, Verifier};
+
+const UNTRUSTED: &str = "untrusted comment: ";
+
+fn b64() -> base64::engine::GeneralPurpose {
+    base64::engine::general_purpose::STANDARD
+}
+
+/// Contents of a signify `.pub` file.
+pub fn public_key(seed: &Seed, realm: &str) -> String {
+    let key = sign::signing_key(seed, realm).verifying_key();
+
+    let mut bytes = b"Ed".to_vec();
+    bytes.extend_from_slice(&sign::key_id(&key));
+    bytes.extend_from_slice(key.as_bytes());
+    format!("{UNTRUSTED}signify public key\n{}\n", b64().encode(bytes))
+}
+
+/// A signify `.sig` file: the signature over the whole message, tagged
+/// with the key number.
+pub fn sign(
+    seed: &Seed,
+    realm: &str,
+    data: &[u8],
+    comment: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    if comment.contains(['\n', '\r']) {
+        return Err("comments must be a single line".into());
+    }
+
+    let key = sign::signing_key(seed, realm);
+    let mut bytes = b"Ed".to_vec();
+    bytes.extend_from_slice(&sign::key_id(&key.verifying_key()));
+    bytes.extend_from_slice(&key.sign(data).to_bytes());
+    Ok(format!("{UNTRUSTED}{comment}\n{}\n", b64().encode(bytes)))
+}
+
+/// Whether `text` looks like a signify `.sig` file (check for minisign
+/// first; its files start the same way).
+pub fn is_signature(text: &[u8]) -> bool {
+    text.starts_with(UNTRUSTED.as_bytes())
+}
+
+pub fn verify(
+    key: &PublicKey,
+    signature: &str,
+    data: &[u8],
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let line = signature
+        .lines()
+        .nth(1)
+        .ok_or("invalid signify signature: expected two lines")?;
+    let bytes = b64().decode(line.trim())?;
+    if bytes.len() != 74 || &bytes[..2] != b"Ed" {
+        return Err("invalid signify signature".into());
+    }
+    if bytes[2..10] != key.id {
+        return Err("signature was made with a different key".into());
+    }
+
+    let signature = Signature::from_slice(&bytes[10..])?;
+    Ok(key.key.verify(data, &signature).is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sign_and_verify() {
+        let seed = Seed::from_passphrase("test").unwrap();
+        let public = public_key(&seed, "realm");
+        assert!(public.starts_with("untrusted comment: signify public key\nRW"));
+
+        let key = PublicKey::parse(&public).unwrap();
+        let signature = sign(&seed, "realm", b"data", "verify with realm.pub").unwrap();
+        assert!(verify(&key, &signature, b"data").unwrap());
+        assert!(!verify(&key, &signature, b"other").unwrap());
+
+        let other = PublicKey::parse(&public_key(&seed, "other")).unwrap();
+        assert!(verify(&other, &signature, b"data").is_err());
+    }
+
+    #[test]
+    fn signature_layout() {
+        let seed = Seed::from_passphrase("test").unwrap();
+        let key = sign::signing_key(&seed, "realm");
+        let signature = sign(&seed, "realm", b"data", "c").unwrap();
+
+        let line = signature.lines().nth(1).unwrap();
+        let bytes = b64().decode(line).unwrap();
+        assert_eq!(&bytes[..2], b"Ed");
+        assert_eq!(bytes[2..10], sign::key_id(&key.verifying_key()));
+        assert_eq!(bytes[10..], key.sign(b"data").to_bytes());
+    }
+}
@@ -1,11 +1,18 @@
+use crate::ec;
 use crate::seed::Seed;
 use ed25519_dalek::SigningKey;
-use ssh_key::{LineEnding, PrivateKey};
+use rand_core::OsRng;
+use ssh_key::certificate::{self, CertType};
+use ssh_key::private::{EcdsaKeypair, EcdsaPrivateKey, RsaKeypair};
+use ssh_key::public::KeyData;
+use ssh_key::{HashAlg, LineEnding, PrivateKey, SshSig};
 use std::io::Write;
+use std::path::Path;
 use std::process::{Command, Stdio};
+use zeroize::Zeroizing;
 
-pub fn derive_private(seed: &Seed, realm: &str) -> String {
-    let raw = seed.derive_32(realm, "ssh");
+fn derive_key(seed: &Seed, realm: &str, label: &str) -> PrivateKey {
+    let raw = seed.derive_32(realm, label);
     let signing_key = SigningKey::from_bytes(&raw);
     let verifying_key = signing_key.verifying_key();
 
@@ -13,21 +20,65 @@ pub fn derive_private(seed: &Seed, realm: &str) -> String {
         public: ssh_key::public::Ed25519PublicKey::from(verifying_key),
         private: ssh_key::private::Ed25519PrivateKey::from(signing_key),
     };
+    PrivateKey::from(keypair)
+}
 
-    let private_key = PrivateKey::from(keypair);
-    private_key
-        .to_openssh(LineEnding::LF)
-        .expect("valid key")
-        .to_string()
+#[derive(Clone, Copy)]
+pub enum KeyType {
+    Ed25519,
+    P256,
+    /// Modulus size in bits, one of `rsa::SIZES`
+    Rsa(usize),
 }
 
-pub fn derive_public(seed: &Seed, realm: &str) -> String {
-    let raw = seed.derive_32(realm, "ssh");
-    let signing_key = SigningKey::from_bytes(&raw);
-    let verifying_key = signing_key.verifying_key();
+fn derive_rsa(seed: &Seed, realm: &str, bits: usize) -> ::rsa::RsaPrivateKey {
+    crate::rsa::generate(seed, realm, &format!("ssh/rsa/{bits}"), bits).expect("supported key size")
+}
+
+/// The realm's user key; Ed25519 unless another type is asked for.
+fn derive_user_key(seed: &Seed, realm: &str, key_type: KeyType) -> PrivateKey {
+    match key_type {
+        KeyType::Ed25519 => derive_key(seed, realm, "ssh"),
+        KeyType::P256 => {
+            let key = ec::derive_p256(seed, realm, "ssh/p256");
+            let keypair = EcdsaKeypair::NistP256 {
+                public: key.verifying_key().to_encoded_point(false),
+                private: EcdsaPrivateKey::from(p256::SecretKey::from(key.as_nonzero_scalar())),
+            };
+            PrivateKey::from(keypair)
+        }
+        KeyType::Rsa(bits) => {
+            let keypair = RsaKeypair::try_from(derive_rsa(seed, realm, bits)).expect("valid key");
+            PrivateKey::from(keypair)
+        }
+    }
+}
+
+pub fn derive_private(seed: &Seed, realm: &str, key_type: KeyType) -> Zeroizing<String> {
+    let private_key = derive_user_key(seed, realm, key_type);
+    private_key.to_openssh(LineEnding::LF).expect("valid key")
+}
+
+/// The same key as `derive_private`, as PKCS#8 PEM.
+pub fn derive_private_pkcs8(seed: &Seed, realm: &str, key_type: KeyType) -> Zeroizing<String> {
+    use ed25519_dalek::pkcs8::EncodePrivateKey;
 
-    let ed25519_pubkey = ssh_key::public::Ed25519PublicKey::from(verifying_key);
-    let public_key = ssh_key::PublicKey::from(ed25519_pubkey);
+    let line_ending = ed25519_dalek::pkcs8::spki::der::pem::LineEnding::LF;
+    match key_type {
+        KeyType::Ed25519 => SigningKey::from_bytes(&seed.derive_32(realm, "ssh"))
+            .to_pkcs8_pem(line_ending)
+            .expect("valid key"),
+        KeyType::P256 => ec::derive_p256(seed, realm, "ssh/p256")
+            .to_pkcs8_pem(line_ending)
+            .expect("valid key"),
+        KeyType::Rsa(bits) => derive_rsa(seed, realm, bits)
+            .to_pkcs8_pem(line_ending)
+            .expect("valid key"),
+    }
+}
+
+pub fn derive_public(seed: &Seed, realm: &str, key_type: KeyType) -> String {
+    let public_key = derive_user_key(seed, realm, key_type).public_key().clone();
     format!(
         "{} 1seed:{}",
         public_key.to_openssh().expect("valid key"),
@@ -35,13 +86,544 @@ pub fn derive_public(seed: &Seed, realm: &str) -> String {
     )
 }
 
+pub fn fingerprint(seed: &Seed, realm: &str) -> String {
+    let public_key = derive_key(seed, realm, "ssh").public_key().clone();
+    public_key.fingerprint(HashAlg::Sha256).to_string()
+}
+
+fn check_host(host: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if host.is_empty() || host.contains('/') || host.contains(char::is_whitespace) {
+        return Err(
+            format!("invalid host '{host}': must be non-empty without '/' or spaces").into(),
+        );
+    }
+    Ok(())
+}
+
+/// Host keys use their own label per host, never the realm's user key.
+fn derive_host_key(
+    seed: &Seed,
+    realm: &str,
+    host: &str,
+) -> Result<PrivateKey, Box<dyn std::error::Error>> {
+    check_host(host)?;
+    let mut key = derive_key(seed, realm, &format!("ssh/host/{host}"));
+    key.set_comment(format!("root@{host}"));
+    Ok(key)
+}
+
+/// `known_hosts` line for the host key; `names` default to the host itself.
+pub fn known_hosts_line(
+    seed: &Seed,
+    realm: &str,
+    host: &str,
+    names: &[String],
+) -> Result<String, Box<dyn std::error::Error>> {
+    let key = derive_host_key(seed, realm, host)?;
+    let mut public_key = key.public_key().clone();
+    public_key.set_comment("");
+
+    let names = if names.is_empty() {
+        host.to_string()
+    } else {
+        names.join(",")
+    };
+    Ok(format!("{names} {}", public_key.to_openssh()?))
+}
+
+/// Write `ssh_host_ed25519_key` (0600) and `.pub` (0644) into `dir`,
+/// replacing any existing host key.
+pub fn install_hostkey(
+    seed: &Seed,
+    realm: &str,
+    host: &str,
+    dir: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let key = derive_host_key(seed, realm, host)?;
+    let private_path = dir.join("ssh_host_ed25519_key");
+    let public_path = dir.join("ssh_host_ed25519_key.pub");
+
+    crate::seed::write_private(&private_path, key.to_openssh(LineEnding::LF)?.as_bytes())?;
+    std::fs::write(
+        &public_path,
+        format!("{}\n", key.public_key().to_openssh()?),
+    )?;
+    #[cfg(unix)]
+    {
+        use std::fs::Permissions;
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&public_path, Permissions::from_mode(0o644))?;
+    }
+    Ok(())
+}
+
+/// CA public key, for `TrustedUserCAKeys` or `@cert-authority` lines.
+pub fn ca_public(seed: &Seed, realm: &str) -> String {
+    let public_key = derive_key(seed, realm, "ssh/ca").public_key().clone();
+    format!(
+        "{} 1seed-ca:{}",
+        public_key.to_openssh().expect("valid key"),
+        realm
+    )
+}
+
+/// Certificate contents, mirroring `ssh-keygen -s` options.
+pub struct CertOptions {
+    pub principals: Vec<String>,
+    pub host: bool,
+    pub valid_after: u64,
+    pub valid_before: u64,
+    pub serial: u64,
+    pub key_id: String,
+    pub critical_options: Vec<(String, String)>,
+    /// `None` uses the ssh-keygen defaults for user certificates
+    pub extensions: Option<Vec<(String, String)>>,
+}
+
+const DEFAULT_USER_EXTENSIONS: &[&str] = &[
+    "permit-X11-forwarding",
+    "permit-agent-forwarding",
+    "permit-port-forwarding",
+    "permit-pty",
+    "permit-user-rc",
+];
+
+/// Sign `public_key` (OpenSSH format) with the realm CA.
+pub fn sign_certificate(
+    seed: &Seed,
+    realm: &str,
+    public_key: &str,
+    opts: &CertOptions,
+) -> Result<String, Box<dyn std::error::Error>> {
+    if opts.principals.is_empty() {
+        return Err("at least one principal is required".into());
+    }
+
+    let subject = ssh_key::PublicKey::from_openssh(public_key.trim())?;
+    let ca = derive_key(seed, realm, "ssh/ca");
+
+    let mut builder = certificate::Builder::new_with_random_nonce(
+        &mut OsRng,
+        subject.key_data().clone(),
+        opts.valid_after,
+        opts.valid_before.min(SSH_KEY_MAX_TIME),
+    )?;
+    builder.serial(opts.serial)?;
+    builder.key_id(opts.key_id.as_str())?;
+    builder.cert_type(if opts.host {
+        CertType::Host
+    } else {
+        CertType::User
+    })?;
+    for principal in &opts.principals {
+        builder.valid_principal(principal.as_str())?;
+    }
+    for (name, value) in &opts.critical_options {
+        builder
+            .critical_option(name.as_str(), value.as_str())
+            .map_err(|_| format!("duplicate critical option '{name}'"))?;
+    }
+
+    match &opts.extensions {
+        Some(extensions) => {
+            for (name, value) in extensions {
+                builder
+                    .extension(name.as_str(), value.as_str())
+                    .map_err(|_| format!("duplicate extension '{name}'"))?;
+            }
+        }
+        None if !opts.host => {
+            for name in DEFAULT_USER_EXTENSIONS {
+                builder.extension(*name, "")?;
+            }
+        }
+        None => {}
+    }
+
+    if !subject.comment().is_empty() {
+        builder.comment(subject.comment())?;
+    }
+
+    let cert = builder.sign(&ca)?;
+    if opts.valid_before == FOREVER {
+        return resign_forever(&cert, &ca);
+    }
+    Ok(cert.to_openssh()?)
+}
+
+/// OpenSSH's `valid_before` for certificates that never expire.
+const FOREVER: u64 = u64::MAX;
+
+/// Latest time ssh-key accepts; it treats validity times as signed.
+const SSH_KEY_MAX_TIME: u64 = i64::MAX as u64;
+
+/// Swap `valid_before` for `FOREVER` and sign again, which ssh-key cannot
+/// do itself. Everything after `valid_before` in the signed part has a known
+/// length, so its offset is found from the end.
+fn resign_forever(
+    cert: &certificate::Certificate,
+    ca: &PrivateKey,
+) -> Result<String, Box<dyn std::error::Error>> {
+    use base64::Engine;
+    use ed25519_dalek::Signer;
+    use ssh_encoding::Encode;
+
+    let mut encoded = Vec::new();
+    cert.encode(&mut encoded)?;
+    let mut tbs = encoded[..encoded.len() - cert.signature().encoded_len_prefixed()?].to_vec();
+
+    let tail = cert.critical_options().encoded_len()?
+        + cert.extensions().encoded_len()?
+        + 4 // reserved, always empty
+        + cert.signature_key().encoded_len_prefixed()?;
+    let at = tbs.len() - tail - 8;
+    if tbs[at..at + 8] != SSH_KEY_MAX_TIME.to_be_bytes() {
+        return Err("unexpected certificate layout".into());
+    }
+    tbs[at..at + 8].copy_from_slice(&FOREVER.to_be_bytes());
+
+    let signature: ssh_key::Signature = ca.try_sign(&tbs)?;
+    let mut out = tbs;
+    signature.encode_prefixed(&mut out)?;
+
+    let mut line = format!(
+        "{} {}",
+        cert.algorithm().to_certificate_type(),
+        base64::engine::general_purpose::STANDARD.encode(out)
+    );
+    if !cert.comment().is_empty() {
+        line.push(' ');
+        line.push_str(cert.comment());
+    }
+    Ok(line)
+}
+
+/// Parse an ssh-keygen style validity interval relative to `now`:
+/// `always`, `+8h`, or `FROM:TO` such as `-5m:+1d2h` or
+/// `20250101Z:forever`. Units are s, m, h, d and w; a bare number is
+/// seconds. Absolute times are `YYYYMMDD[HHMM[SS]]Z`, in UTC.
+pub fn parse_validity(spec: &str, now: u64) -> Result<(u64, u64), Box<dyn std::error::Error>> {
+    if spec == "always" || spec == "forever" {
+        return Ok((0, FOREVER));
+    }
+
+    let (from, to) = match spec.split_once(':') {
+        Some((from, to)) => {
+            let from = if from == "always" {
+                0
+            } else {
+                parse_time(from, now)?
+            };
+            let to = if to == "forever" {
+                FOREVER
+            } else {
+                parse_time(to, now)?
+            };
+            (from, to)
+        }
+        None => (now, parse_time(spec, now)?),
+    };
+
+    if to <= from {
+        return Err(format!("invalid validity '{spec}': ends before it starts").into());
+    }
+    Ok((from, to))
+}
+
+fn parse_time(spec: &str, now: u64) -> Result<u64, Box<dyn std::error::Error>> {
+    if spec.starts_with(|c: char| c.is_ascii_digit()) {
+        absolute_time(spec)
+    } else {
+        relative_time(spec, now)
+    }
+}
+
+/// `YYYYMMDD[HHMM[SS]]Z`. ssh-keygen reads these without the `Z` as local
+/// time; that form is refused rather than guessed at.
+fn absolute_time(spec: &str) -> Result<u64, Box<dyn std::error::Error>> {
+    let invalid = || format!("invalid time '{spec}': expected YYYYMMDD[HHMM[SS]]Z");
+
+    let digits = spec.strip_suffix(['Z', 'z']).ok_or_else(|| {
+        format!("invalid time '{spec}': absolute times must end in Z (UTC), e.g. {spec}Z")
+    })?;
+    if !matches!(digits.len(), 8 | 12 | 14) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid().into());
+    }
+    // two digits always fit a u8
+    let field = |at: usize| {
+        digits
+            .get(at..at + 2)
+            .map_or(0, |d| d.parse::<u8>().expect("two digits"))
+    };
+
+    let time = x509_cert::der::DateTime::new(
+        digits[..4].parse()?,
+        field(4),
+        field(6),
+        field(8),
+        field(10),
+        field(12),
+    )
+    .map_err(|_| invalid())?;
+    Ok(time.unix_duration().as_secs())
+}
+
+fn relative_time(spec: &str, now: u64) -> Result<u64, Box<dyn std::error::Error>> {
+    let invalid = || format!("invalid time '{spec}': expected +N or -N with units s/m/h/d/w");
+
+    let (negative, rest) = match spec.as_bytes().first() {
+        Some(b'+') => (false, &spec[1..]),
+        Some(b'-') => (true, &spec[1..]),
+        _ => return Err(invalid().into()),
+    };
+    let total = parse_duration(rest).map_err(|_| invalid())?;
+
+    Ok(if negative {
+        now.saturating_sub(total)
+    } else {
+        now.saturating_add(total)
+    })
+}
+
+/// Seconds in a duration like `90`, `1h` or `1d12h`.
+pub fn parse_duration(spec: &str) -> Result<u64, Box<dyn std::error::Error>> {
+    let invalid = || format!("invalid duration '{spec}': expected N with units s/m/h/d/w");
+
+    let mut total: u64 = 0;
+    let mut number = String::new();
+    for c in spec.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 3600,
+            'd' => 86400,
+            'w' => 604800,
+            _ => return Err(invalid().into()),
+        };
+        let n: u64 = number.parse().map_err(|_| invalid())?;
+        total = total.saturating_add(n.saturating_mul(unit));
+        number.clear();
+    }
+    if !number.is_empty() {
+        total = total.saturating_add(number.parse::<u64>().map_err(|_| invalid())?);
+    } else if spec.is_empty() {
+        return Err(invalid().into());
+    }
+
+    Ok(total)
+}
+
+/// The realm key type an OpenSSH public key would be, if it is a type
+/// we derive.
+pub fn key_type_of(key: &ssh_key::PublicKey) -> Option<KeyType> {
+    match key.key_data() {
+        KeyData::Ed25519(_) => Some(KeyType::Ed25519),
+        KeyData::Ecdsa(ssh_key::public::EcdsaPublicKey::NistP256(_)) => Some(KeyType::P256),
+        KeyData::Rsa(rsa) => {
+            let bits = rsa.n.as_positive_bytes()?.len() * 8;
+            crate::rsa::SIZES
+                .contains(&bits)
+                .then_some(KeyType::Rsa(bits))
+        }
+        _ => None,
+    }
+}
+
+/// Whether `key` is the realm's user key (any comment).
+pub fn is_user_key(seed: &Seed, realm: &str, key: &ssh_key::PublicKey) -> bool {
+    key_type_of(key).is_some_and(|key_type| {
+        derive_user_key(seed, realm, key_type)
+            .public_key()
+            .key_data()
+            == key.key_data()
+    })
+}
+
+/// Armored SSHSIG signature over `data`, as made by `ssh-keygen -Y sign`.
+pub fn sign_data(
+    seed: &Seed,
+    realm: &str,
+    key_type: KeyType,
+    namespace: &str,
+    data: &[u8],
+) -> Result<String, Box<dyn std::error::Error>> {
+    if namespace.is_empty() {
+        return Err("namespace must not be empty".into());
+    }
+    let signature = match key_type {
+        // ssh-key 0.6 rebuilds RSA private keys from the wrong primes and
+        // fails to sign, so sign the SSHSIG data with the rsa crate
+        KeyType::Rsa(bits) => {
+            use ::rsa::signature::{SignatureEncoding, Signer};
+
+            let private = derive_rsa(seed, realm, bits);
+            let public = ssh_key::public::RsaPublicKey::try_from(private.to_public_key())?;
+            let signed_data = SshSig::signed_data(namespace, HashAlg::Sha512, data)?;
+            let signature = ::rsa::pkcs1v15::SigningKey::<sha2::Sha512>::new(private)
+                .sign(&signed_data)
+                .to_vec();
+            let algorithm = ssh_key::Algorithm::Rsa {
+                hash: Some(HashAlg::Sha512),
+            };
+            SshSig::new(
+                KeyData::Rsa(public),
+                namespace,
+                HashAlg::Sha512,
+                ssh_key::Signature::new(algorithm, signature)?,
+            )?
+        }
+        _ => derive_user_key(seed, realm, key_type).sign(namespace, HashAlg::Sha512, data)?,
+    };
+    Ok(signature.to_pem(LineEnding::LF)?)
+}
+
+/// Check an armored SSHSIG signature over `data` in `namespace` and return
+/// the key that made it; whether that key is trusted is up to the caller.
+pub fn check_signature(
+    signature: &str,
+    namespace: &str,
+    data: &[u8],
+) -> Result<ssh_key::PublicKey, Box<dyn std::error::Error>> {
+    let signature = SshSig::from_pem(signature.trim())?;
+    if signature.namespace() != namespace {
+        return Err(format!(
+            "signature is for namespace '{}', not '{namespace}'",
+            signature.namespace()
+        )
+        .into());
+    }
+    let key = ssh_key::PublicKey::from(signature.public_key().clone());
+    key.verify(namespace, data, &signature)
+        .map_err(|_| "invalid signature")?;
+    Ok(key)
+}
+
+/// The principals of the first `allowed_signers` entry that lets `key`
+/// sign in `namespace` (for `identity`, if given). Entries with options
+/// other than `namespaces` are skipped: certificate authorities and
+/// validity periods are not supported.
+pub fn find_signer(
+    allowed_signers: &str,
+    key: &ssh_key::PublicKey,
+    namespace: &str,
+    identity: Option<&str>,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    for (number, line) in allowed_signers.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid = || format!("allowed_signers line {}: invalid entry", number + 1);
+
+        let (principals, rest) = line.split_once(char::is_whitespace).ok_or_else(invalid)?;
+        let rest = rest.trim_start();
+        let (options, entry_key) = match ssh_key::PublicKey::from_openssh(rest) {
+            Ok(entry_key) => ("", entry_key),
+            Err(_) => {
+                let (options, rest) = split_options(rest).ok_or_else(invalid)?;
+                let entry_key =
+                    ssh_key::PublicKey::from_openssh(rest.trim_start()).map_err(|_| invalid())?;
+                (options, entry_key)
+            }
+        };
+
+        if entry_key.key_data() != key.key_data() {
+            continue;
+        }
+        if let Some(identity) = identity {
+            if !matches_pattern_list(principals, identity) {
+                continue;
+            }
+        }
+        let mut allowed = true;
+        for option in split_list(options) {
+            match option.split_once('=') {
+                Some((name, value)) if name.eq_ignore_ascii_case("namespaces") => {
+                    allowed &= matches_pattern_list(value.trim_matches('"'), namespace);
+                }
+                _ => allowed = false,
+            }
+        }
+        if allowed {
+            return Ok(Some(principals.to_string()));
+        }
+    }
+    Ok(None)
+}
+
+/// Split the options field off an `allowed_signers` entry: it ends at the
+/// first whitespace outside double quotes.
+fn split_options(text: &str) -> Option<(&str, &str)> {
+    let mut quoted = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => return Some((&text[..i], &text[i..])),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Comma-separated items, leaving commas inside double quotes alone.
+fn split_list(text: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut quoted = false;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                items.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if start < text.len() {
+        items.push(&text[start..]);
+    }
+    items
+}
+
+/// OpenSSH pattern lists: comma-separated `*`/`?` globs, where a match on
+/// a `!`-negated pattern rejects the name outright.
+fn matches_pattern_list(list: &str, name: &str) -> bool {
+    let mut matched = false;
+    for pattern in list.split(',') {
+        match pattern.strip_prefix('!') {
+            Some(negated) if glob(negated.as_bytes(), name.as_bytes()) => return false,
+            Some(_) => {}
+            None => matched |= glob(pattern.as_bytes(), name.as_bytes()),
+        }
+    }
+    matched
+}
+
+fn glob(pattern: &[u8], name: &[u8]) -> bool {
+    match (pattern.first(), name.first()) {
+        (None, None) => true,
+        (Some(b'*'), _) => {
+            glob(&pattern[1..], name) || (!name.is_empty() && glob(pattern, &name[1..]))
+        }
+        (Some(b'?'), Some(_)) => glob(&pattern[1..], &name[1..]),
+        (Some(p), Some(n)) if p == n => glob(&pattern[1..], &name[1..]),
+        _ => false,
+    }
+}
+
 pub fn add_to_agent(
     seed: &Seed,
     realm: &str,
+    key_type: KeyType,
     lifetime: Option<u32>,
     confirm: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let private_key = derive_private(seed, realm);
+    let private_key = derive_private(seed, realm, key_type);
 
     let mut args = vec!["-".to_string()];
 
@@ -75,3 +657,147 @@ pub fn add_to_agent(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validity_intervals() {
+        let now = 1_000_000;
+
+        assert_eq!(parse_validity("+8h", now).unwrap(), (now, now + 8 * 3600));
+        assert_eq!(
+            parse_validity("-5m:+1d2h", now).unwrap(),
+            (now - 300, now + 86400 + 7200)
+        );
+        assert_eq!(parse_validity("+90", now).unwrap(), (now, now + 90));
+        assert_eq!(parse_validity("always", now).unwrap(), (0, u64::MAX));
+        assert_eq!(
+            parse_validity("20240101Z:20240102123000Z", now).unwrap(),
+            (1_704_067_200, 1_704_198_600)
+        );
+        assert_eq!(
+            parse_validity("-1h:forever", now).unwrap(),
+            (now - 3600, u64::MAX)
+        );
+
+        assert!(parse_validity("8h", now).is_err());
+        assert!(parse_validity("+5x", now).is_err());
+        assert!(parse_validity("+1h:-1h", now).is_err());
+        assert!(parse_validity("20240101", now).is_err());
+        assert!(parse_validity("20241301Z", now).is_err());
+    }
+
+    #[test]
+    fn certificate_valid_forever() {
+        use base64::Engine;
+        use ed25519_dalek::Verifier;
+
+        let seed = Seed::from_passphrase("test").unwrap();
+        let subject = derive_public(&seed, "user", KeyType::Ed25519);
+        let (valid_after, valid_before) = parse_validity("always", 0).unwrap();
+        let opts = CertOptions {
+            principals: vec!["alice".to_string()],
+            host: false,
+            valid_after,
+            valid_before,
+            serial: 0,
+            key_id: "alice".to_string(),
+            critical_options: vec![],
+            extensions: None,
+        };
+        let cert = sign_certificate(&seed, "realm", &subject, &opts).unwrap();
+        let blob = base64::engine::general_purpose::STANDARD
+            .decode(cert.split(' ').nth(1).unwrap())
+            .unwrap();
+
+        // the signature blob is string("ssh-ed25519") string(64 bytes)
+        let (tbs, signature) = blob.split_at(blob.len() - 87);
+        let signature = ed25519_dalek::Signature::from_slice(&signature[23..]).unwrap();
+        let ca = derive_key(&seed, "realm", "ssh/ca");
+        let ca = ca.public_key().key_data().ed25519().unwrap().0;
+        let ca = ed25519_dalek::VerifyingKey::from_bytes(&ca).unwrap();
+        assert!(ca.verify(tbs, &signature).is_ok());
+        assert!(tbs.windows(16).any(|w| w == [[0; 8], [0xff; 8]].concat()));
+    }
+
+    #[test]
+    fn host_keys_separate_from_user_key() {
+        let seed = Seed::from_passphrase("test").unwrap();
+
+        let web1 = known_hosts_line(&seed, "realm", "web1", &[]).unwrap();
+        let web2 = known_hosts_line(&seed, "realm", "web2", &[]).unwrap();
+        let user = derive_public(&seed, "realm", KeyType::Ed25519);
+        let user_key = user.split(' ').nth(1).unwrap();
+
+        assert!(web1.starts_with("web1 ssh-ed25519 "));
+        assert_ne!(web1.split(' ').nth(2), web2.split(' ').nth(2));
+        assert_ne!(web1.split(' ').nth(2), Some(user_key));
+        assert!(known_hosts_line(&seed, "realm", "a/b", &[]).is_err());
+    }
+
+    #[test]
+    fn certificate_signed_by_ca() {
+        let seed = Seed::from_passphrase("test").unwrap();
+        let subject = derive_public(&seed, "user", KeyType::Ed25519);
+
+        let opts = CertOptions {
+            principals: vec!["alice".to_string()],
+            host: false,
+            valid_after: 0,
+            valid_before: 2_000_000,
+            serial: 7,
+            key_id: "alice".to_string(),
+            critical_options: vec![],
+            extensions: None,
+        };
+        let cert = sign_certificate(&seed, "realm", &subject, &opts).unwrap();
+        let cert = ssh_key::Certificate::from_openssh(&cert).unwrap();
+
+        let ca = ssh_key::PublicKey::from_openssh(&ca_public(&seed, "realm")).unwrap();
+        let ca_fp = ca.fingerprint(HashAlg::Sha256);
+        assert!(cert.validate_at(1_000_000, [&ca_fp]).is_ok());
+        assert_eq!(cert.serial(), 7);
+        assert!(cert.extensions().contains_key("permit-pty"));
+    }
+
+    #[test]
+    fn sshsig_round_trip() {
+        let seed = Seed::from_passphrase("test").unwrap();
+        let signature = sign_data(&seed, "realm", KeyType::Ed25519, "git", b"commit").unwrap();
+        assert!(signature.starts_with("-----BEGIN SSH SIGNATURE-----\n"));
+
+        let key = check_signature(&signature, "git", b"commit").unwrap();
+        assert!(is_user_key(&seed, "realm", &key));
+        assert!(!is_user_key(&seed, "other", &key));
+        assert!(check_signature(&signature, "file", b"commit").is_err());
+        assert!(check_signature(&signature, "git", b"other").is_err());
+    }
+
+    #[test]
+    fn allowed_signers_entries() {
+        let seed = Seed::from_passphrase("test").unwrap();
+        let public = derive_public(&seed, "realm", KeyType::Ed25519);
+        let key = ssh_key::PublicKey::from_openssh(&public).unwrap();
+        let allowed = format!(
+            "# comment\n\
+             bob@example.com {other}\n\
+             *@example.com,!eve@example.com namespaces=\"git,file\" {public}\n",
+            other = derive_public(&seed, "other", KeyType::Ed25519),
+        );
+
+        let find = |namespace, identity| find_signer(&allowed, &key, namespace, identity).unwrap();
+        assert_eq!(
+            find("git", Some("alice@example.com")).as_deref(),
+            Some("*@example.com,!eve@example.com")
+        );
+        assert!(find("git", None).is_some());
+        assert!(find("git", Some("eve@example.com")).is_none());
+        assert!(find("email", None).is_none());
+
+        let with_ca = format!("* cert-authority {public}\n");
+        assert!(find_signer(&with_ca, &key, "git", None).unwrap().is_none());
+        assert!(find_signer("garbage\n", &key, "git", None).is_err());
+    }
+}
@@ -65,7 +65,7 @@ pub fn update(check_only: bool) -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-fn fetch_latest_version() -> Result<String, Box<dyn std::error::Error>> {
+pub fn fetch_latest_version() -> Result<String, Box<dyn std::error::Error>> {
     let url = format!("https://api.github.com/repos/{}/releases/latest", REPO);
 
     let response = ureq::get(&url).set("User-Agent", "1seed-updater").call()?;
@@ -152,6 +152,6 @@ fn extract_from_targz(
     Err(format!("Binary {} not found in archive", binary_name).into())
 }
 
-fn is_writable(path: &Path) -> bool {
+pub fn is_writable(path: &Path) -> bool {
     fs::OpenOptions::new().append(true).open(path).is_ok()
 }
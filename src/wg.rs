@@ -0,0 +1,240 @@
+use crate::age::x25519_public;
+use crate::seed::Seed;
+use base64::Engine;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::path::Path;
+use zeroize::Zeroizing;
+
+/// Mesh description rendered by `wg config`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    /// Default ListenPort for every host
+    pub listen_port: Option<u16>,
+    /// Default PersistentKeepalive towards every peer
+    pub persistent_keepalive: Option<u16>,
+    #[serde(default)]
+    pub dns: Vec<String>,
+    #[serde(rename = "peer", default)]
+    pub peers: Vec<Peer>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Peer {
+    pub name: String,
+    pub address: String,
+    pub endpoint: Option<String>,
+    /// Defaults to the host part of `address` as a /32 or /128
+    pub allowed_ips: Option<Vec<String>>,
+    pub listen_port: Option<u16>,
+}
+
+impl Manifest {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = std::fs::read_to_string(path)?;
+        let manifest: Manifest =
+            toml::from_str(&content).map_err(|e| format!("{}: {e}", path.display()))?;
+
+        let mut names = HashSet::new();
+        for peer in &manifest.peers {
+            check_name(&peer.name)?;
+            if !names.insert(peer.name.as_str()) {
+                return Err(format!("duplicate peer '{}' in manifest", peer.name).into());
+            }
+        }
+
+        Ok(manifest)
+    }
+}
+
+fn check_name(name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if name.is_empty() || name.contains('/') {
+        return Err(format!("invalid peer name '{name}': must be non-empty without '/'").into());
+    }
+    Ok(())
+}
+
+fn derive_secret(seed: &Seed, realm: &str, node: &str) -> Zeroizing<[u8; 32]> {
+    let mut raw = seed.derive_32(realm, &format!("wg/{node}"));
+    // clamp like `wg genkey` so the printed key is canonical
+    raw[0] &= 248;
+    raw[31] &= 127;
+    raw[31] |= 64;
+    raw
+}
+
+pub fn derive_private(
+    seed: &Seed,
+    realm: &str,
+    node: &str,
+) -> Result<Zeroizing<String>, Box<dyn std::error::Error>> {
+    check_name(node)?;
+    let raw = derive_secret(seed, realm, node);
+    Ok(Zeroizing::new(
+        base64::engine::general_purpose::STANDARD.encode(*raw),
+    ))
+}
+
+pub fn derive_public(
+    seed: &Seed,
+    realm: &str,
+    node: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    check_name(node)?;
+    let raw = derive_secret(seed, realm, node);
+    Ok(base64::engine::general_purpose::STANDARD.encode(x25519_public(&raw)))
+}
+
+/// Preshared key for a pair of nodes; both sides derive the same key.
+pub fn derive_psk(
+    seed: &Seed,
+    realm: &str,
+    a: &str,
+    b: &str,
+) -> Result<Zeroizing<String>, Box<dyn std::error::Error>> {
+    check_name(a)?;
+    check_name(b)?;
+    if a == b {
+        return Err("preshared key needs two different peers".into());
+    }
+
+    let (first, second) = if a < b { (a, b) } else { (b, a) };
+    let raw = seed.derive_32(realm, &format!("wg/psk/{first}/{second}"));
+    Ok(Zeroizing::new(
+        base64::engine::general_purpose::STANDARD.encode(*raw),
+    ))
+}
+
+/// Render a complete wg-quick config for `host`, peering with every other
+/// node in the manifest.
+pub fn render_config(
+    seed: &Seed,
+    realm: &str,
+    manifest: &Manifest,
+    host: &str,
+) -> Result<Zeroizing<String>, Box<dyn std::error::Error>> {
+    let me = manifest
+        .peers
+        .iter()
+        .find(|p| p.name == host)
+        .ok_or_else(|| format!("host '{host}' not found in manifest"))?;
+
+    // sized up front so the buffer holding the private key never reallocates
+    let mut out = Zeroizing::new(String::with_capacity(512 * (manifest.peers.len() + 1)));
+    writeln!(out, "# generated by 1seed for {host} (realm: {realm})")?;
+    writeln!(out, "[Interface]")?;
+    writeln!(
+        out,
+        "PrivateKey = {}",
+        derive_private(seed, realm, host)?.as_str()
+    )?;
+    writeln!(out, "Address = {}", me.address)?;
+    if let Some(port) = me.listen_port.or(manifest.listen_port) {
+        writeln!(out, "ListenPort = {port}")?;
+    }
+    if !manifest.dns.is_empty() {
+        writeln!(out, "DNS = {}", manifest.dns.join(", "))?;
+    }
+
+    for peer in manifest.peers.iter().filter(|p| p.name != host) {
+        let allowed_ips = match &peer.allowed_ips {
+            Some(ips) => ips.join(", "),
+            None => host_route(&peer.address),
+        };
+
+        writeln!(out)?;
+        writeln!(out, "[Peer]")?;
+        writeln!(out, "# {}", peer.name)?;
+        writeln!(
+            out,
+            "PublicKey = {}",
+            derive_public(seed, realm, &peer.name)?
+        )?;
+        writeln!(
+            out,
+            "PresharedKey = {}",
+            derive_psk(seed, realm, host, &peer.name)?.as_str()
+        )?;
+        writeln!(out, "AllowedIPs = {allowed_ips}")?;
+        if let Some(endpoint) = &peer.endpoint {
+            writeln!(out, "Endpoint = {endpoint}")?;
+        }
+        if let Some(keepalive) = manifest.persistent_keepalive {
+            writeln!(out, "PersistentKeepalive = {keepalive}")?;
+        }
+    }
+
+    Ok(out)
+}
+
+fn host_route(address: &str) -> String {
+    let ip = address.split('/').next().unwrap_or(address);
+    if ip.contains(':') {
+        format!("{ip}/128")
+    } else {
+        format!("{ip}/32")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"
+listen_port = 51820
+persistent_keepalive = 25
+
+[[peer]]
+name = "web1"
+address = "10.0.0.1/24"
+endpoint = "web1.example.com:51820"
+
+[[peer]]
+name = "web2"
+address = "10.0.0.2/24"
+allowed_ips = ["10.0.0.2/32", "192.168.1.0/24"]
+"#;
+
+    #[test]
+    fn psk_symmetric() {
+        let seed = Seed::from_passphrase("test").unwrap();
+
+        let ab = derive_psk(&seed, "realm", "a", "b").unwrap();
+        let ba = derive_psk(&seed, "realm", "b", "a").unwrap();
+        let ac = derive_psk(&seed, "realm", "a", "c").unwrap();
+
+        assert_eq!(ab.as_str(), ba.as_str());
+        assert_ne!(ab.as_str(), ac.as_str());
+    }
+
+    #[test]
+    fn configs_pair_up() {
+        let seed = Seed::from_passphrase("test").unwrap();
+        let manifest: Manifest = toml::from_str(MANIFEST).unwrap();
+
+        let web1 = render_config(&seed, "realm", &manifest, "web1").unwrap();
+        let web2 = render_config(&seed, "realm", &manifest, "web2").unwrap();
+
+        let web1_pub = derive_public(&seed, "realm", "web1").unwrap();
+        let psk = derive_psk(&seed, "realm", "web1", "web2").unwrap();
+
+        assert!(web2.contains(&format!("PublicKey = {web1_pub}")));
+        assert!(web1.contains(&format!("PresharedKey = {}", psk.as_str())));
+        assert!(web2.contains(&format!("PresharedKey = {}", psk.as_str())));
+        assert!(web1.contains("AllowedIPs = 10.0.0.2/32, 192.168.1.0/24"));
+        assert!(web2.contains("AllowedIPs = 10.0.0.1/32"));
+        assert!(web2.contains("Endpoint = web1.example.com:51820"));
+        assert!(web1.contains("ListenPort = 51820"));
+    }
+
+    #[test]
+    fn unknown_host_rejected() {
+        let seed = Seed::from_passphrase("test").unwrap();
+        let manifest: Manifest = toml::from_str(MANIFEST).unwrap();
+
+        assert!(render_config(&seed, "realm", &manifest, "db1").is_err());
+    }
+}